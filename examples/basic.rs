@@ -18,6 +18,10 @@ const F64_VALUE_CUSTOM: f64 = 0.0;
 #[const_tweaker::tweak]
 const F64_VALUE_DEFAULT: f64 = 0.0;
 
+// Logarithmic slider for values spanning orders of magnitude
+#[const_tweaker::tweak(min = 0.01, max = 100.0, scale = "log")]
+const F64_VALUE_LOG: f64 = 1.0;
+
 // Default values for integer sliders
 #[const_tweaker::tweak]
 const I8_VALUE_DEFAULT: i8 = 0;
@@ -54,6 +58,7 @@ fn main() {
             F32_VALUE_DEFAULT,
             F64_VALUE_CUSTOM,
             F64_VALUE_DEFAULT,
+            F64_VALUE_LOG,
             I8_VALUE_DEFAULT,
             U8_VALUE_DEFAULT,
             I16_VALUE_DEFAULT,
@@ -11,8 +11,8 @@ use darling::FromMeta;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote, ToTokens};
 use syn::{
-    parse_macro_input, spanned::Spanned, AttributeArgs, Error, Expr, ItemConst, Type,
-    Type::Reference,
+    parse_macro_input, spanned::Spanned, AttributeArgs, Error, Expr, ItemConst, Lit, NestedMeta,
+    Path, Type, Type::Reference,
 };
 
 type TokenStream2 = proc_macro2::TokenStream;
@@ -29,6 +29,47 @@ where
     max: Option<T>,
     #[darling(default)]
     step: Option<T>,
+    #[darling(default)]
+    allow_out_of_range: bool,
+    #[darling(default)]
+    scale: Option<Scale>,
+    #[darling(default)]
+    exponent: Option<Exponent>,
+    #[darling(default)]
+    validate: Option<Path>,
+}
+
+/// How the position of the slider maps to the value.
+#[derive(Debug, PartialEq)]
+enum Scale {
+    Linear,
+    Log,
+    Pow,
+}
+
+impl FromMeta for Scale {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "linear" => Ok(Scale::Linear),
+            "log" => Ok(Scale::Log),
+            "pow" => Ok(Scale::Pow),
+            _ => Err(darling::Error::unknown_value(value)),
+        }
+    }
+}
+
+/// The exponent of a `pow` scale, which can be written as both an integer and a float.
+#[derive(Debug)]
+struct Exponent(f64);
+
+impl FromMeta for Exponent {
+    fn from_value(value: &Lit) -> darling::Result<Self> {
+        match value {
+            Lit::Int(int) => Ok(Exponent(int.base10_parse()?)),
+            Lit::Float(float) => Ok(Exponent(float.base10_parse()?)),
+            _ => Err(darling::Error::unexpected_lit_type(value)),
+        }
+    }
 }
 
 impl<T: FromMeta> Metadata<T> {
@@ -51,19 +92,48 @@ fn field_init<T>(
     default_step: T,
 ) -> Result<TokenStream2, TokenStream>
 where
-    T: FromMeta + ToTokens,
+    T: FromMeta + ToTokens + PartialOrd + Default,
 {
     let min = metadata.min.unwrap_or(default_min);
     let max = metadata.max.unwrap_or(default_max);
     let step = metadata.step.unwrap_or(default_step);
+    let allow_out_of_range = metadata.allow_out_of_range;
+    let validate = match metadata.validate {
+        Some(path) => quote! { Some(#path) },
+        None => quote! { None },
+    };
+    let scale = match (metadata.scale, metadata.exponent) {
+        (None, None) | (Some(Scale::Linear), None) => quote! { const_tweaker::Scale::Linear },
+        (Some(Scale::Log), None) => {
+            // The logarithm of 0 or a negative number can't be taken
+            if min <= T::default() {
+                return metadata_error("`scale = \"log\"` requires a `min` larger than 0");
+            }
+
+            quote! { const_tweaker::Scale::Log }
+        }
+        (Some(Scale::Pow), exponent) => {
+            let exponent = exponent.map(|exponent| exponent.0).unwrap_or(2.0);
+            if exponent <= 0.0 {
+                return metadata_error("`exponent` must be larger than 0");
+            }
+
+            quote! { const_tweaker::Scale::Pow(#exponent) }
+        }
+        (_, Some(_)) => return metadata_error("`exponent` requires `scale = \"pow\"`"),
+    };
 
     Ok(match field_type {
         "f32" => quote! {
             const_tweaker::Field::F32 {
                 value: #default_value as f32,
+                default: #default_value as f32,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -73,9 +143,13 @@ where
         "f64" => quote! {
             const_tweaker::Field::F64 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -85,9 +159,13 @@ where
         "i8" => quote! {
             const_tweaker::Field::I8 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -97,9 +175,13 @@ where
         "u8" => quote! {
             const_tweaker::Field::U8 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -109,9 +191,13 @@ where
         "i16" => quote! {
             const_tweaker::Field::I16 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -121,9 +207,13 @@ where
         "u16" => quote! {
             const_tweaker::Field::U16 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -133,9 +223,13 @@ where
         "i32" => quote! {
             const_tweaker::Field::I32 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -145,9 +239,13 @@ where
         "u32" => quote! {
             const_tweaker::Field::U32 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -157,9 +255,13 @@ where
         "i64" => quote! {
             const_tweaker::Field::I64 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -169,9 +271,13 @@ where
         "u64" => quote! {
             const_tweaker::Field::U64 {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -181,9 +287,13 @@ where
         "usize" => quote! {
             const_tweaker::Field::Usize {
                 value: #default_value,
+                default: #default_value,
                 min: #min,
                 max: #max,
                 step: #step,
+                allow_out_of_range: #allow_out_of_range,
+                scale: #scale,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -193,6 +303,8 @@ where
         "bool" => quote! {
             const_tweaker::Field::Bool {
                 value: #default_value,
+                default: #default_value,
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -202,6 +314,8 @@ where
         "str" => quote! {
             const_tweaker::Field::String {
                 value: #default_value.to_string(),
+                default: #default_value.to_string(),
+                validate: #validate,
 
                 module: module_path!().to_string(),
                 file: file!().to_string(),
@@ -236,7 +350,7 @@ fn field_name(field_type: &str, ty: &Type) -> Result<TokenStream2, TokenStream>
 
 /// Get the field type as a string.
 fn field_type(ty: &Type) -> Result<String, TokenStream> {
-    if let Type::Path(type_path) = &*ty {
+    if let Type::Path(type_path) = ty {
         match type_path.path.get_ident() {
             Some(type_ident) => Ok(type_ident.to_string()),
             None => mismatching_type_error(ty),
@@ -257,21 +371,51 @@ fn mismatching_type_error<T>(ty: &Type) -> Result<T, TokenStream> {
     ))
 }
 
+/// The error message when the combination of metadata is invalid.
+fn metadata_error<T>(message: &str) -> Result<T, TokenStream> {
+    Err(TokenStream::from(
+        Error::new(proc_macro2::Span::call_site(), message).to_compile_error(),
+    ))
+}
+
+/// Split the `on_change` function from the other arguments, it's handled the same for all types.
+fn split_on_change(args: AttributeArgs) -> Result<(Option<Path>, AttributeArgs), TokenStream> {
+    let (on_change, args): (Vec<_>, Vec<_>) = args.into_iter().partition(
+        |arg| matches!(arg, NestedMeta::Meta(meta) if meta.path().is_ident("on_change")),
+    );
+
+    let on_change = match on_change.last() {
+        Some(NestedMeta::Meta(meta)) => {
+            Some(Path::from_meta(meta).map_err(|e| TokenStream::from(e.write_errors()))?)
+        }
+        _ => None,
+    };
+
+    Ok((on_change, args))
+}
+
 /// Proc macro call but with a result, which allows the use of `?`.
 fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, TokenStream> {
     let name = input.ident;
+    let (on_change, args) = split_on_change(args)?;
+    let on_change = match on_change {
+        Some(path) => quote! {
+            const_tweaker::subscribe(concat!(module_path!(), "::", stringify!(#name)), #path);
+        },
+        None => quote! {},
+    };
     let init_name = format_ident!("{}_init", name);
     let ty = if let Reference(type_ref) = *input.ty {
         type_ref.elem
     } else {
         input.ty
     };
-    let field_type = field_type(&*ty)?;
-    let field_name = field_name(&field_type, &*ty)?;
+    let field_type = field_type(&ty)?;
+    let field_name = field_name(&field_type, &ty)?;
     let field_init = match &*field_type {
         "f32" => field_init::<f32>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             0.0,
@@ -280,7 +424,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "f64" => field_init::<f64>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             0.0,
@@ -289,7 +433,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "i8" => field_init::<i8>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             i8::MIN,
@@ -298,7 +442,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "u8" => field_init::<u8>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             u8::MIN,
@@ -307,7 +451,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "i16" => field_init::<i16>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             i16::MIN,
@@ -316,7 +460,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "u16" => field_init::<u16>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             u16::MIN,
@@ -325,7 +469,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "i32" => field_init::<i32>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             i32::MIN,
@@ -334,7 +478,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "u32" => field_init::<u32>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             u32::MIN,
@@ -343,7 +487,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "i64" => field_init::<i64>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             i64::MIN,
@@ -352,7 +496,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "u64" => field_init::<u64>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             u64::MIN,
@@ -361,7 +505,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "usize" => field_init::<usize>(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             usize::MIN,
@@ -370,7 +514,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "bool" => field_init(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             0,
@@ -379,7 +523,7 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         )?,
         "str" => field_init(
             &field_type,
-            &*ty,
+            &ty,
             Metadata::from_attributes(args)?,
             *input.expr,
             0,
@@ -393,6 +537,14 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
 
     let type_impls = if field_type == "str" {
         quote! {
+            impl const_tweaker::Tweakable for #name {
+                type Type = String;
+
+                fn key(&self) -> &'static str {
+                    concat!(module_path!(), "::", stringify!(#name))
+                }
+            }
+
             impl std::convert::From<#name> for &#ty {
                 fn from(original: #name) -> &'static #ty {
                     original.get()
@@ -401,6 +553,14 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         }
     } else {
         quote! {
+            impl const_tweaker::Tweakable for #name {
+                type Type = #ty;
+
+                fn key(&self) -> &'static str {
+                    concat!(module_path!(), "::", stringify!(#name))
+                }
+            }
+
             impl std::convert::From<#name> for #ty {
                 fn from(original: #name) -> #ty {
                     *original.get()
@@ -409,6 +569,21 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         }
     };
 
+    // Values overridden in tests take precedence
+    let scoped_override = if field_type == "str" {
+        quote! {
+            if let Some(value) = const_tweaker::scoped_override::<String>(concat!(module_path!(), "::", stringify!(#name))) {
+                return value.as_str();
+            }
+        }
+    } else {
+        quote! {
+            if let Some(value) = const_tweaker::scoped_override::<#ty>(concat!(module_path!(), "::", stringify!(#name))) {
+                return value;
+            }
+        }
+    };
+
     let result = quote! {
         #[allow(non_camel_case_types)]
         #[doc(hidden)]
@@ -419,6 +594,8 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
 
         impl #name {
             pub fn get(&self) -> &'static #ty {
+                #scoped_override
+
                 // Retrieve the value from the datastore and unwrap it
                 match const_tweaker::DATA.get(concat!(module_path!(), "::", stringify!(#name))).expect("Value should have been added already").value() {
                     #field_name { ref value, .. } => unsafe {
@@ -429,6 +606,29 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
                     _ => panic!("Type mismatch, this probably means there's a duplicate value in the map, please report an issue")
                 }
             }
+
+            /// The generation of this value, it increases every time the value changes.
+            pub fn generation(&self) -> u64 {
+                const_tweaker::field_generation(concat!(module_path!(), "::", stringify!(#name)))
+            }
+
+            /// Whether the value changed since the generation in `last_seen`, which is updated to
+            /// the current generation so the next call only reports new changes.
+            pub fn changed_since(&self, last_seen: &mut u64) -> bool {
+                let generation = self.generation();
+                if generation == *last_seen {
+                    false
+                } else {
+                    *last_seen = generation;
+
+                    true
+                }
+            }
+
+            /// Set the value back to the value as declared in the source.
+            pub fn reset(&self) {
+                const_tweaker::reset(concat!(module_path!(), "::", stringify!(#name))).expect("Value should have been added already");
+            }
         }
 
         // Automatically unwrap the primitive value from the struct when dereferencing
@@ -462,6 +662,9 @@ fn tweak_impl(args: AttributeArgs, input: ItemConst) -> Result<TokenStream, Toke
         fn #init_name() {
             // Insert the value when the module is loaded
             const_tweaker::DATA.insert(concat!(module_path!(), "::", stringify!(#name)), #field_init);
+            // Replace it with the value passed at startup
+            const_tweaker::apply_override(concat!(module_path!(), "::", stringify!(#name)));
+            #on_change
         }
     };
 
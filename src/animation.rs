@@ -0,0 +1,443 @@
+use crate::{api, notify, Descriptor, Error, Value, DATA};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    /// The animated values by key.
+    static ref ANIMATIONS: DashMap<String, Animation> = DashMap::new();
+    /// The time in seconds the animations are at.
+    static ref CLOCK: Mutex<f64> = Mutex::new(0.0);
+}
+
+/// How the value moves from a keyframe to the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// Move at a constant speed.
+    Linear,
+    /// Keep the value until the next keyframe.
+    Step,
+    /// Start slow and speed up.
+    EaseIn,
+    /// Start fast and slow down.
+    EaseOut,
+    /// Start slow, speed up and slow down again.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map the progress between two keyframes, both from `0` to `1`.
+    fn apply(self, progress: f64) -> f64 {
+        match self {
+            Easing::Linear => progress,
+            Easing::Step => 0.0,
+            Easing::EaseIn => progress * progress,
+            Easing::EaseOut => progress * (2.0 - progress),
+            Easing::EaseInOut => progress * progress * (3.0 - 2.0 * progress),
+        }
+    }
+}
+
+/// The value of an animated constant at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Seconds on the clock advanced with [`advance_clock`].
+    pub time: f64,
+    /// The value at the time, rounded for integers.
+    pub value: f64,
+    /// How the value moves to the next keyframe.
+    pub easing: Easing,
+}
+
+/// The keyframes of a constant & the value it had before it was animated.
+#[derive(Debug, Clone)]
+struct Animation {
+    /// Sorted by time.
+    keyframes: Vec<Keyframe>,
+    /// The value from before the animation started, it's restored when the animation stops.
+    static_value: Value,
+    /// The value last set by the animation, to tell it apart from values set elsewhere.
+    value: Option<Value>,
+}
+
+impl Animation {
+    /// Interpolate the value between the keyframes around the time.
+    fn value_at(&self, time: f64) -> f64 {
+        let next = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time);
+
+        match next {
+            // Before the first keyframe
+            Some(0) => self.keyframes[0].value,
+            Some(next) => {
+                let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+                let progress = from
+                    .easing
+                    .apply((time - from.time) / (to.time - from.time));
+
+                from.value + (to.value - from.value) * progress
+            }
+            // After the last keyframe
+            None => self.keyframes[self.keyframes.len() - 1].value,
+        }
+    }
+}
+
+/// Let a numeric constant follow a curve through the keyframes instead of having a fixed value.
+///
+/// Reading the constant returns the value interpolated at the time of the clock, which is only
+/// moved by [`advance_clock`] & [`set_clock`], so the animation runs in the time of the game.
+/// Before the first keyframe the value of the first one is used and after the last keyframe the
+/// value of the last one.
+///
+/// The keyframes can also be edited on the website. Changes made there aren't added to the
+/// history and the changes output shows the value from before the animation started.
+///
+/// Subscribers are notified when the animation starts and when it stops, not every time the clock
+/// moves. Setting the constant while it's animated sets it until the clock moves again, and
+/// changes the value that's restored when the animation stops: presets & exports also use that
+/// value.
+///
+/// ```rust
+/// use const_tweaker::{Easing, Keyframe};
+///
+/// #[const_tweaker::tweak(min = 0.0, max = 10.0)]
+/// const SPEED: f64 = 1.0;
+///
+/// # let key = concat!(module_path!(), "::SPEED");
+/// const_tweaker::animate(
+///     key,
+///     vec![
+///         Keyframe { time: 0.0, value: 0.0, easing: Easing::Linear },
+///         Keyframe { time: 2.0, value: 10.0, easing: Easing::Linear },
+///     ],
+/// )
+/// .unwrap();
+///
+/// // In the game loop
+/// const_tweaker::advance_clock(0.5);
+/// assert_eq!(*SPEED, 2.5);
+///
+/// // Set right away, but the animation takes over again when the clock moves
+/// const_tweaker::set(key, 3.0).unwrap();
+/// assert_eq!(*SPEED, 3.0);
+/// const_tweaker::advance_clock(0.5);
+/// assert_eq!(*SPEED, 5.0);
+///
+/// const_tweaker::stop_animation(key).unwrap();
+/// assert_eq!(*SPEED, 3.0);
+/// ```
+pub fn animate(key: &str, mut keyframes: Vec<Keyframe>) -> Result<(), Error> {
+    let rejected = |reason: &str| Error::Rejected {
+        key: key.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let static_value = {
+        let field = DATA
+            .get(key)
+            .ok_or_else(|| Error::UnknownKey(key.to_string()))?;
+        let descriptor = Descriptor::new(key, &field);
+
+        let (min, max) = match (&descriptor.min, &descriptor.max) {
+            (Some(min), Some(max)) => (
+                min.to_json().as_f64().unwrap_or(f64::MIN),
+                max.to_json().as_f64().unwrap_or(f64::MAX),
+            ),
+            _ => return Err(rejected("only numbers can be animated")),
+        };
+
+        if keyframes.is_empty() {
+            return Err(rejected("an animation needs at least one keyframe"));
+        }
+        if let Some(keyframe) = keyframes
+            .iter()
+            .find(|keyframe| !keyframe.time.is_finite() || !keyframe.value.is_finite())
+        {
+            return Err(rejected(&format!(
+                "keyframe {} at {} is not a number",
+                keyframe.value, keyframe.time
+            )));
+        }
+        if !descriptor.allow_out_of_range {
+            if let Some(keyframe) = keyframes
+                .iter()
+                .find(|keyframe| keyframe.value < min || keyframe.value > max)
+            {
+                return Err(rejected(&format!(
+                    "keyframe {} is outside of the range {} to {}",
+                    keyframe.value, min, max
+                )));
+            }
+        }
+
+        descriptor.value
+    };
+
+    keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    // Keep the original value when the keyframes of an animated value are replaced
+    let static_value = ANIMATIONS
+        .get(key)
+        .map(|animation| animation.static_value.clone())
+        .unwrap_or(static_value);
+    ANIMATIONS.insert(
+        key.to_string(),
+        Animation {
+            keyframes,
+            static_value,
+            value: None,
+        },
+    );
+
+    // Show the animated value immediately instead of after the clock is advanced
+    update();
+    notify(key);
+
+    Ok(())
+}
+
+/// Stop animating a constant and put back the value from before the animation started.
+pub fn stop_animation(key: &str) -> Result<(), Error> {
+    let (_, animation) = ANIMATIONS.remove(key).ok_or_else(|| Error::Rejected {
+        key: key.to_string(),
+        reason: "the value isn't animated".to_string(),
+    })?;
+
+    // Skip the validation, the validation function might reject the value by now and the
+    // constant would be stuck at the animated value
+    DATA.get_mut(key)
+        .ok_or_else(|| Error::UnknownKey(key.to_string()))?
+        .restore_value(animation.static_value);
+
+    // The field must be unlocked before notifying, so the callbacks can read it
+    notify(key);
+
+    Ok(())
+}
+
+/// The keyframes of all animated constants by key.
+pub fn animations() -> Vec<(String, Vec<Keyframe>)> {
+    let mut animations = ANIMATIONS
+        .iter()
+        .map(|kv| (kv.key().clone(), kv.value().keyframes.clone()))
+        .collect::<Vec<_>>();
+    animations.sort_by(|a, b| a.0.cmp(&b.0));
+
+    animations
+}
+
+/// Move the clock of the animations forward, call this once per frame with the frame time in
+/// seconds.
+pub fn advance_clock(seconds: f64) {
+    *CLOCK.lock().unwrap() += seconds;
+
+    update();
+}
+
+/// Set the clock of the animations to a time in seconds, e.g. `0.0` to restart them.
+pub fn set_clock(seconds: f64) {
+    *CLOCK.lock().unwrap() = seconds;
+
+    update();
+}
+
+/// The time in seconds the animations are at.
+pub fn clock() -> f64 {
+    *CLOCK.lock().unwrap()
+}
+
+/// The value of the constant from before it was animated, `None` when it's not animated.
+pub(crate) fn static_value(key: &str) -> Option<Value> {
+    ANIMATIONS
+        .get(key)
+        .map(|animation| animation.static_value.clone())
+}
+
+/// Keep a value set while the constant is animated as the value to restore when the animation
+/// stops, instead of losing it the next time the clock moves.
+pub(crate) fn changed(key: &str) {
+    let value = match DATA.get(key) {
+        Some(field) => field.current_value(),
+        None => return,
+    };
+
+    if let Some(mut animation) = ANIMATIONS.get_mut(key) {
+        if animation.value.as_ref() != Some(&value) {
+            animation.static_value = value;
+        }
+    }
+}
+
+/// Set all animated values to their value at the time of the clock.
+fn update() {
+    let time = clock();
+
+    // Calculate all values first so the animations aren't locked while the fields are
+    let values = ANIMATIONS
+        .iter()
+        .map(|kv| (kv.key().clone(), kv.value().value_at(time)))
+        .collect::<Vec<_>>();
+
+    for (key, value) in values {
+        let value = match DATA.get(&*key) {
+            Some(field) => field.value_from_json(field.current_value().number_json(value)),
+            None => continue,
+        };
+        // Values rejected by the validation function keep the previous value
+        let value = match value {
+            Ok(value) if api::set_checked(&key, value.clone()).is_ok() => value,
+            _ => continue,
+        };
+
+        // The field must be unlocked first, `changed` locks them the other way around
+        if let Some(mut animation) = ANIMATIONS.get_mut(&*key) {
+            animation.value = Some(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_history, subscribe, test_field};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    lazy_static::lazy_static! {
+        /// The clock is shared by all animations, so the tests moving it can't run in parallel.
+        static ref CLOCK_TEST: Mutex<()> = Mutex::new(());
+    }
+
+    fn keyframe(time: f64, value: f64, easing: Easing) -> Keyframe {
+        Keyframe {
+            time,
+            value,
+            easing,
+        }
+    }
+
+    fn value(key: &str) -> Value {
+        DATA.get(key).unwrap().current_value()
+    }
+
+    #[test]
+    fn interpolation() {
+        let animation = Animation {
+            keyframes: vec![
+                keyframe(1.0, 0.0, Easing::Linear),
+                keyframe(3.0, 4.0, Easing::EaseIn),
+                keyframe(5.0, 8.0, Easing::Step),
+                keyframe(6.0, 2.0, Easing::Linear),
+            ],
+            static_value: Value::F64(0.0),
+            value: None,
+        };
+
+        assert_eq!(animation.value_at(0.0), 0.0);
+        assert_eq!(animation.value_at(2.0), 2.0);
+        assert_eq!(animation.value_at(4.0), 5.0);
+        assert_eq!(animation.value_at(5.5), 8.0);
+        assert_eq!(animation.value_at(10.0), 2.0);
+    }
+
+    #[test]
+    fn rejected_keyframes() {
+        test_field("animation::tests::REJECTED", None);
+        let key = "animation::tests::REJECTED";
+
+        assert!(animate(key, vec![]).is_err());
+        assert!(animate(key, vec![keyframe(0.0, 11.0, Easing::Linear)]).is_err());
+        assert!(animate(key, vec![keyframe(f64::NAN, 1.0, Easing::Linear)]).is_err());
+        assert!(matches!(
+            animate(
+                "animation::tests::MISSING",
+                vec![keyframe(0.0, 1.0, Easing::Linear)]
+            ),
+            Err(Error::UnknownKey(_))
+        ));
+        assert!(animations().iter().all(|(animated, _)| animated != key));
+        assert!(stop_animation(key).is_err());
+    }
+
+    #[test]
+    fn notified_on_start_and_stop() {
+        let _clock = CLOCK_TEST.lock().unwrap();
+        test_field("animation::tests::NOTIFIED", None);
+        let key = "animation::tests::NOTIFIED";
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        subscribe(key, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        set_clock(0.0);
+        animate(
+            key,
+            vec![
+                keyframe(0.0, 0.0, Easing::Linear),
+                keyframe(1.0, 10.0, Easing::Linear),
+            ],
+        )
+        .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        advance_clock(0.5);
+        assert_eq!(value(key), Value::F64(5.0));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        stop_animation(key).unwrap();
+        assert_eq!(value(key), Value::F64(1.0));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn set_while_animated() {
+        let _history = lock_history();
+        let _clock = CLOCK_TEST.lock().unwrap();
+        test_field("animation::tests::SET", None);
+        let key = "animation::tests::SET";
+
+        set_clock(0.0);
+        animate(key, vec![keyframe(0.0, 5.0, Easing::Linear)]).unwrap();
+        crate::set(key, 3.0).unwrap();
+        assert_eq!(value(key), Value::F64(3.0));
+        assert_eq!(static_value(key), Some(Value::F64(3.0)));
+
+        advance_clock(0.1);
+        assert_eq!(value(key), Value::F64(5.0));
+
+        stop_animation(key).unwrap();
+        assert_eq!(value(key), Value::F64(3.0));
+        assert_eq!(static_value(key), None);
+    }
+
+    #[test]
+    fn restored_when_no_longer_valid() {
+        let _clock = CLOCK_TEST.lock().unwrap();
+        // Accepts anything at first, rejects the value from before the animation later
+        static STRICT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+        test_field(
+            "animation::tests::RESTORED",
+            Some(|value| {
+                if STRICT.load(Ordering::SeqCst) && *value < 2.0 {
+                    Err("too slow".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        );
+        let key = "animation::tests::RESTORED";
+
+        animate(key, vec![keyframe(0.0, 5.0, Easing::Linear)]).unwrap();
+        STRICT.store(true, Ordering::SeqCst);
+
+        stop_animation(key).unwrap();
+        assert_eq!(value(key), Value::F64(1.0));
+    }
+}
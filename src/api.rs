@@ -0,0 +1,431 @@
+use crate::{history, notify, Field, Origin, Scale, Value, DATA};
+use std::{any, cmp::Ordering, convert::TryFrom, error, fmt};
+
+/// Errors returned when accessing values by key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// There's no constant registered with the key.
+    UnknownKey(String),
+    /// The type of the value doesn't match the type of the constant.
+    TypeMismatch {
+        key: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The value is outside of the range of the slider or the validation function rejected it.
+    Rejected { key: String, reason: String },
+    /// There's no change with the id in the history.
+    UnknownChange(u64),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownKey(key) => write!(f, "no tweakable constant named \"{}\"", key),
+            Error::TypeMismatch {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "type mismatch for \"{}\": expected {}, found {}",
+                key, expected, found
+            ),
+            Error::Rejected { key, reason } => {
+                write!(f, "value for \"{}\" is rejected: {}", key, reason)
+            }
+            Error::UnknownChange(id) => write!(f, "no change with id {} in the history", id),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Description of a tweakable constant with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Descriptor {
+    /// The full path of the constant, e.g. `my_game::physics::GRAVITY`.
+    pub key: String,
+    /// The Rust type of the constant as written in the source.
+    pub type_name: &'static str,
+    /// The current value.
+    pub value: Value,
+    /// The value as declared in the source.
+    pub default: Value,
+    /// Minimum value of the slider, `None` for `bool` & `&str`.
+    pub min: Option<Value>,
+    /// Maximum value of the slider, `None` for `bool` & `&str`.
+    pub max: Option<Value>,
+    /// Step increase of the slider, `None` for `bool` & `&str`.
+    pub step: Option<Value>,
+    /// How the position of the slider maps to the value.
+    pub scale: Scale,
+    /// Whether values outside of the slider range are accepted.
+    pub allow_out_of_range: bool,
+    /// Rust module location.
+    pub module: String,
+    /// Rust file location with the line number.
+    pub file: String,
+    /// Rust line number in file.
+    pub line: u32,
+}
+
+impl Descriptor {
+    /// Describe the field.
+    pub(crate) fn new(key: &str, field: &Field) -> Self {
+        let (min, max, step, scale, allow_out_of_range) = match_numeric!(field,
+            {
+                min,
+                max,
+                step,
+                scale,
+                allow_out_of_range,
+                ..
+            } => (
+                Some(Value::from(*min)),
+                Some(Value::from(*max)),
+                Some(Value::from(*step)),
+                *scale,
+                *allow_out_of_range,
+            ),
+            Field::Bool { .. } | Field::String { .. } => (None, None, None, Scale::Linear, false),
+        );
+
+        Descriptor {
+            key: key.to_string(),
+            type_name: field.type_name(),
+            value: field.current_value(),
+            default: field.default_value(),
+            min,
+            max,
+            step,
+            scale,
+            allow_out_of_range,
+            module: field.module_path().to_string(),
+            file: field.file(),
+            line: field.line_number(),
+        }
+    }
+}
+
+/// Set the value of a constant by it's full path.
+///
+/// The value must be the exact type of the constant, it goes through the same range check and
+/// validation as values set from the web interface, and notifies subscribers when it's accepted.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0.0, max = 1.0)]
+/// const VALUE: f64 = 0.0;
+///
+/// # let key = concat!(module_path!(), "::VALUE");
+/// // The key is the full path of the constant, e.g. "my_game::VALUE"
+/// const_tweaker::set(key, 0.5).unwrap();
+/// assert_eq!(*VALUE, 0.5);
+///
+/// // Errors are returned for type mismatches & values outside of the slider range
+/// assert!(const_tweaker::set(key, 0.5f32).is_err());
+/// assert!(const_tweaker::set(key, 2.0).is_err());
+/// ```
+pub fn set<T>(key: &str, value: T) -> Result<(), Error>
+where
+    T: Into<Value>,
+{
+    set_from(key, value.into(), Origin::Api)
+}
+
+/// Set the value of a constant and record where the change came from in the history.
+pub(crate) fn set_from(key: &str, value: Value, origin: Origin) -> Result<(), Error> {
+    let old = set_checked(key, value.clone())?;
+
+    // Setting the same value again isn't a change
+    if !old.is_same(&value) {
+        history::record(key, old, value, origin);
+        notify(key);
+    }
+
+    Ok(())
+}
+
+/// Set the value of a constant when it's the same type, within range and valid, returns the old
+/// value.
+///
+/// The validation function is called without the constant locked, so it can read other constants.
+pub(crate) fn set_checked(key: &str, value: Value) -> Result<Value, Error> {
+    let rejected = |reason| Error::Rejected {
+        key: key.to_string(),
+        reason,
+    };
+
+    let validate = {
+        let field = DATA
+            .get(key)
+            .ok_or_else(|| Error::UnknownKey(key.to_string()))?;
+
+        if field.type_name() != value.type_name() {
+            return Err(Error::TypeMismatch {
+                key: key.to_string(),
+                expected: field.type_name(),
+                found: value.type_name(),
+            });
+        }
+
+        field.check_value(&value).map_err(rejected)?
+    };
+    if let Some(validate) = validate {
+        validate().map_err(rejected)?;
+    }
+
+    let mut field = DATA.get_mut(key).expect("Could not get item from map");
+    let old = field.current_value();
+    field.restore_value(value);
+
+    Ok(old)
+}
+
+/// Get the value of a constant by it's full path.
+///
+/// ```rust
+/// #[const_tweaker::tweak]
+/// const VALUE: &str = "Hi";
+///
+/// # let key = concat!(module_path!(), "::VALUE");
+/// // The key is the full path of the constant, e.g. "my_game::VALUE"
+/// assert_eq!(const_tweaker::get::<String>(key).unwrap(), "Hi");
+///
+/// // The types are named as they're written in the source
+/// # use const_tweaker::Error;
+/// assert_eq!(
+///     const_tweaker::get::<bool>(key),
+///     Err(Error::TypeMismatch {
+///         key: key.to_string(),
+///         expected: "bool",
+///         found: "&str"
+///     })
+/// );
+/// ```
+pub fn get<T>(key: &str) -> Result<T, Error>
+where
+    T: TryFrom<Value, Error = Value>,
+{
+    let value = DATA
+        .get(key)
+        .map(|field| field.current_value())
+        .ok_or_else(|| Error::UnknownKey(key.to_string()))?;
+
+    T::try_from(value).map_err(|value| Error::TypeMismatch {
+        key: key.to_string(),
+        expected: expected_type_name::<T>(),
+        found: value.type_name(),
+    })
+}
+
+/// The name of the type a value is converted to, as it's named by [`Value::type_name`].
+fn expected_type_name<T>() -> &'static str
+where
+    T: TryFrom<Value, Error = Value>,
+{
+    // Find the variant that converts to the type
+    let samples = [
+        Value::F32(0.0),
+        Value::F64(0.0),
+        Value::I8(0),
+        Value::U8(0),
+        Value::I16(0),
+        Value::U16(0),
+        Value::I32(0),
+        Value::U32(0),
+        Value::I64(0),
+        Value::U64(0),
+        Value::Usize(0),
+        Value::Bool(false),
+        Value::String(String::new()),
+    ];
+
+    samples
+        .iter()
+        .find(|sample| T::try_from((*sample).clone()).is_ok())
+        .map(Value::type_name)
+        .unwrap_or_else(any::type_name::<T>)
+}
+
+/// Set the value of a constant back to the value as declared in the source.
+pub fn reset(key: &str) -> Result<(), Error> {
+    let (old, new) = {
+        let mut field = DATA
+            .get_mut(key)
+            .ok_or_else(|| Error::UnknownKey(key.to_string()))?;
+        let old = field.current_value();
+        field.reset();
+
+        (old, field.current_value())
+    };
+
+    if !old.is_same(&new) {
+        history::record(key, old, new, Origin::Reset);
+        notify(key);
+    }
+
+    Ok(())
+}
+
+/// Describe all tweakable constants, sorted by module and line number.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0, max = 10)]
+/// const VALUE: u8 = 5;
+///
+/// for descriptor in const_tweaker::iter() {
+///     println!(
+///         "{} ({}) = {}, declared at {}",
+///         descriptor.key, descriptor.type_name, descriptor.value, descriptor.file
+///     );
+/// }
+/// ```
+pub fn iter() -> impl Iterator<Item = Descriptor> {
+    let mut descriptors = DATA
+        .iter()
+        .map(|kv| Descriptor::new(kv.key(), kv.value()))
+        .collect::<Vec<_>>();
+
+    descriptors.sort_by(|a, b| match a.module.cmp(&b.module) {
+        Ordering::Equal => a.line.cmp(&b.line),
+        ordering => ordering,
+    });
+
+    descriptors.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_history, subscribe, test_field};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Count the notifications for the key.
+    fn count_notifications(key: &str) -> Arc<AtomicUsize> {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        subscribe(key, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        calls
+    }
+
+    #[test]
+    fn set_and_get() {
+        let _history = lock_history();
+        test_field("api::tests::SET", None);
+        let calls = count_notifications("api::tests::SET");
+
+        assert_eq!(set("api::tests::SET", 2.5), Ok(()));
+        assert_eq!(get::<f64>("api::tests::SET"), Ok(2.5));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Setting the same value again isn't a change
+        assert_eq!(set("api::tests::SET", 2.5), Ok(()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn set_rejected() {
+        let _history = lock_history();
+        test_field(
+            "api::tests::REJECTED",
+            Some(|value| {
+                if *value == 5.0 {
+                    Err("not five".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        );
+        let calls = count_notifications("api::tests::REJECTED");
+
+        assert!(matches!(
+            set("api::tests::REJECTED", 20.0),
+            Err(Error::Rejected { .. })
+        ));
+        assert_eq!(
+            set("api::tests::REJECTED", 5.0),
+            Err(Error::Rejected {
+                key: "api::tests::REJECTED".to_string(),
+                reason: "not five".to_string()
+            })
+        );
+        assert_eq!(
+            set("api::tests::REJECTED", 5.0f32),
+            Err(Error::TypeMismatch {
+                key: "api::tests::REJECTED".to_string(),
+                expected: "f64",
+                found: "f32"
+            })
+        );
+
+        assert_eq!(get::<f64>("api::tests::REJECTED"), Ok(1.0));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn unknown_key() {
+        let key = "api::tests::UNKNOWN";
+        let unknown = Error::UnknownKey(key.to_string());
+
+        assert_eq!(set(key, 1.0), Err(unknown.clone()));
+        assert_eq!(get::<f64>(key), Err(unknown.clone()));
+        assert_eq!(reset(key), Err(unknown));
+    }
+
+    #[test]
+    fn reset_to_default() {
+        let _history = lock_history();
+        test_field("api::tests::RESET", None);
+        let calls = count_notifications("api::tests::RESET");
+
+        set("api::tests::RESET", 3.0).unwrap();
+        assert_eq!(reset("api::tests::RESET"), Ok(()));
+        assert_eq!(get::<f64>("api::tests::RESET"), Ok(1.0));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Resetting the default isn't a change
+        assert_eq!(reset("api::tests::RESET"), Ok(()));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn nan_is_the_same() {
+        let _history = lock_history();
+        test_field("api::tests::NAN", None);
+        if let Field::F64 {
+            allow_out_of_range, ..
+        } = &mut *DATA.get_mut("api::tests::NAN").unwrap()
+        {
+            *allow_out_of_range = true;
+        }
+        let calls = count_notifications("api::tests::NAN");
+
+        set("api::tests::NAN", f64::NAN).unwrap();
+        set("api::tests::NAN", f64::NAN).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn validation_reads_constants() {
+        let _history = lock_history();
+        // Validating while the constant is locked would never finish
+        test_field(
+            "api::tests::SELF_READING",
+            Some(|value| match get::<f64>("api::tests::SELF_READING") {
+                Ok(old) if (value - old).abs() <= 1.0 => Ok(()),
+                _ => Err("changed too much".to_string()),
+            }),
+        );
+
+        assert_eq!(set("api::tests::SELF_READING", 2.0), Ok(()));
+        assert!(set("api::tests::SELF_READING", 4.0).is_err());
+        assert_eq!(get::<f64>("api::tests::SELF_READING"), Ok(2.0));
+    }
+}
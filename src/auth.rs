@@ -0,0 +1,326 @@
+use std::{env, future::Future, pin::Pin};
+use tide::{Next, Request, Response};
+
+/// The header used by clients other than the browser to pass the token.
+const HEADER: &str = "x-const-tweaker-token";
+
+/// The cookie the browser stores the token in after opening the URL with it.
+const COOKIE: &str = "const_tweaker_token";
+
+lazy_static::lazy_static! {
+    /// The secret every request must contain, `None` when authentication is disabled.
+    static ref TOKEN: Option<String> = match env::var("CONST_TWEAKER_TOKEN") {
+        Ok(token) if !token.is_empty() => Some(token),
+        _ => env::var("CONST_TWEAKER_AUTH")
+            .is_ok_and(|value| !value.is_empty() && value != "0" && value != "false")
+            .then(generate_token),
+    };
+}
+
+/// The token required to access the web interface, `None` when authentication is disabled.
+///
+/// Authentication is enabled by setting the `CONST_TWEAKER_AUTH=1` environment variable, which
+/// generates a new token every time the application starts, or by passing a fixed token with
+/// `CONST_TWEAKER_TOKEN=<secret>`.
+pub fn token() -> Option<&'static str> {
+    TOKEN.as_deref()
+}
+
+/// Reject all requests that don't contain the token.
+///
+/// The token is accepted from the `token` query parameter, the cookie that's set when it's
+/// passed that way, or the `X-Const-Tweaker-Token` header.
+pub(crate) fn check_token<'a>(
+    request: Request<()>,
+    next: Next<'a, ()>,
+) -> Pin<Box<dyn Future<Output = Response> + Send + 'a>> {
+    Box::pin(async move {
+        let token = match token() {
+            Some(token) => token,
+            None => return next.run(request).await,
+        };
+
+        let source = find_token(
+            request.uri().query(),
+            request.header("cookie"),
+            request.header(HEADER),
+            token,
+        );
+
+        match source {
+            // Store the token in the browser so the URL without it keeps working
+            Some(Source::Query) => next.run(request).await.set_header(
+                "set-cookie",
+                format!(
+                    "{}={}; HttpOnly; SameSite=Strict; Path=/",
+                    COOKIE,
+                    percent_encode(token)
+                ),
+            ),
+            Some(Source::Cookie) | Some(Source::Header) => next.run(request).await,
+            None => Response::new(401).body_string(
+                "Unauthorized, open the URL with the token printed at startup".to_string(),
+            ),
+        }
+    })
+}
+
+/// Where in the request the token was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Query,
+    Cookie,
+    Header,
+}
+
+/// Find the token in the query, the cookies or the header of a request, `None` when it's in none
+/// of them.
+fn find_token(
+    query: Option<&str>,
+    cookies: Option<&str>,
+    header: Option<&str>,
+    token: &str,
+) -> Option<Source> {
+    // The values in the query & the cookie are percent encoded, so tokens can contain any character
+    let matches = |value: Option<&str>| {
+        value
+            .and_then(percent_decode)
+            .is_some_and(|value| constant_time_eq(&value, token))
+    };
+    let header_matches = header.is_some_and(|header| constant_time_eq(header, token));
+
+    if matches(query.and_then(|query| find_pair(query, '&', "token"))) {
+        Some(Source::Query)
+    } else if matches(cookies.and_then(|cookies| find_pair(cookies, ';', COOKIE))) {
+        Some(Source::Cookie)
+    } else if header_matches {
+        Some(Source::Header)
+    } else {
+        None
+    }
+}
+
+/// Generate a random token of 32 hexadecimal characters.
+fn generate_token() -> String {
+    // Use the random number generator of the operating system, which is suitable for secrets
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("Could not generate a random token");
+
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Find the value of a `name=value` pair in a list separated by the separator.
+fn find_pair<'a>(list: &'a str, separator: char, name: &str) -> Option<&'a str> {
+    list.split(separator)
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Encode all characters except letters, digits and `-._~` as `%XX`, for putting the token in a
+/// URL or a cookie.
+pub(crate) fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Decode the `%XX` escapes and `+` as a space, `None` when an escape or the UTF-8 is invalid.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = vec![];
+
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        match byte {
+            b'%' => {
+                let hex = tail
+                    .get(..2)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &tail[2..];
+            }
+            b'+' => {
+                bytes.push(b' ');
+                rest = tail;
+            }
+            byte => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Compare the strings without returning early, so the token can't be guessed by timing it.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn token_from_query() {
+        assert_eq!(
+            find_token(
+                Some("token=0123456789abcdef0123456789abcdef"),
+                None,
+                None,
+                TOKEN
+            ),
+            Some(Source::Query)
+        );
+        assert_eq!(
+            find_token(
+                Some("view=all&token=0123456789abcdef0123456789abcdef"),
+                None,
+                None,
+                TOKEN
+            ),
+            Some(Source::Query)
+        );
+    }
+
+    #[test]
+    fn token_from_cookie() {
+        assert_eq!(
+            find_token(
+                None,
+                Some("theme=dark; const_tweaker_token=0123456789abcdef0123456789abcdef"),
+                None,
+                TOKEN
+            ),
+            Some(Source::Cookie)
+        );
+    }
+
+    #[test]
+    fn token_from_header() {
+        assert_eq!(
+            find_token(None, None, Some(TOKEN), TOKEN),
+            Some(Source::Header)
+        );
+    }
+
+    #[test]
+    fn query_is_preferred() {
+        // The cookie is only set again when the token is in the query
+        assert_eq!(
+            find_token(
+                Some("token=0123456789abcdef0123456789abcdef"),
+                Some("const_tweaker_token=0123456789abcdef0123456789abcdef"),
+                Some(TOKEN),
+                TOKEN
+            ),
+            Some(Source::Query)
+        );
+    }
+
+    #[test]
+    fn missing_token() {
+        assert_eq!(find_token(None, None, None, TOKEN), None);
+        assert_eq!(
+            find_token(Some("view=all"), Some("theme=dark"), None, TOKEN),
+            None
+        );
+    }
+
+    #[test]
+    fn wrong_token() {
+        assert_eq!(find_token(Some("token=wrong"), None, None, TOKEN), None);
+        assert_eq!(
+            find_token(None, Some("const_tweaker_token="), None, TOKEN),
+            None
+        );
+        assert_eq!(find_token(None, None, Some(&TOKEN[1..]), TOKEN), None);
+        // Only the name of the pair counts, not the value of another pair
+        assert_eq!(
+            find_token(
+                Some("other=token=0123456789abcdef0123456789abcdef"),
+                Some("other_token=0123456789abcdef0123456789abcdef"),
+                None,
+                TOKEN
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn pairs() {
+        assert_eq!(find_pair("a=1&b=2", '&', "b"), Some("2"));
+        assert_eq!(find_pair("a=1; b=2", ';', "b"), Some("2"));
+        assert_eq!(find_pair("a=1&b", '&', "b"), None);
+        assert_eq!(find_pair("", '&', "b"), None);
+    }
+
+    #[test]
+    fn encoded_token() {
+        let token = "a+b %c&d=e;f";
+        let encoded = percent_encode(token);
+        assert_eq!(encoded, "a%2Bb%20%25c%26d%3De%3Bf");
+
+        let query = format!("view=all&token={}", encoded);
+        assert_eq!(
+            find_token(Some(&query), None, None, token),
+            Some(Source::Query)
+        );
+        let cookies = format!("theme=dark; const_tweaker_token={}", encoded);
+        assert_eq!(
+            find_token(None, Some(&cookies), None, token),
+            Some(Source::Cookie)
+        );
+        assert_eq!(
+            find_token(None, None, Some(token), token),
+            Some(Source::Header)
+        );
+        // A raw `+` in the query is a space
+        assert_eq!(find_token(Some("token=a+b"), None, None, "a+b"), None);
+        assert_eq!(
+            find_token(Some("token=a+b"), None, None, "a b"),
+            Some(Source::Query)
+        );
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(percent_decode("abc").as_deref(), Some("abc"));
+        assert_eq!(percent_decode("%41%2b%2B+").as_deref(), Some("A++ "));
+        assert_eq!(percent_decode("%C3%A9").as_deref(), Some("\u{e9}"));
+        assert_eq!(percent_decode("%"), None);
+        assert_eq!(percent_decode("%4"), None);
+        assert_eq!(percent_decode("%zz"), None);
+        assert_eq!(percent_decode("%+1"), None);
+        assert_eq!(percent_decode("%FF"), None);
+    }
+
+    #[test]
+    fn comparison() {
+        assert!(constant_time_eq("", ""));
+        assert!(constant_time_eq(TOKEN, TOKEN));
+        assert!(!constant_time_eq(TOKEN, "0123456789abcdef0123456789abcdee"));
+        assert!(!constant_time_eq(TOKEN, &TOKEN[..31]));
+    }
+
+    #[test]
+    fn generated_tokens() {
+        let token = generate_token();
+
+        assert_eq!(token.len(), 32);
+        assert!(token.bytes().all(|byte| byte.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token());
+    }
+}
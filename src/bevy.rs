@@ -0,0 +1,169 @@
+use crate::{api, change_queue, stop_server, Descriptor, Error, Value};
+use ::bevy::{app::AppExit, prelude::*};
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
+
+/// Ties the web server to the lifecycle of the app and sends a [`TweakChanged`] event for every
+/// value that changed.
+///
+/// The web server is already started before `main` unless running headless, it's stopped when the
+/// app exits.
+/// It's not started again, so an app created after another app with the plugin exited in the same
+/// process doesn't have a web interface.
+///
+/// ```rust
+/// use bevy::prelude::*;
+/// use const_tweaker::bevy::{ConstTweakerPlugin, TweakChanged, Tweaks};
+///
+/// #[const_tweaker::tweak(min = 0.0, max = 100.0)]
+/// const GRAVITY: f64 = 9.8;
+///
+/// #[derive(Resource, Default)]
+/// struct Gravity(f64);
+///
+/// fn update_gravity(
+///     tweaks: Res<Tweaks>,
+///     mut changes: EventReader<TweakChanged>,
+///     mut gravity: ResMut<Gravity>,
+/// ) {
+///     for change in changes.read() {
+///         if change.key.ends_with("::GRAVITY") {
+///             gravity.0 = tweaks.get(&change.key).unwrap();
+///         }
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugins(ConstTweakerPlugin)
+///     .init_resource::<Gravity>()
+///     .add_systems(Update, update_gravity);
+///
+/// # let key = concat!(module_path!(), "::GRAVITY");
+/// // Changing the value from anywhere, including the web interface, sends an event
+/// const_tweaker::set(key, 20.0).unwrap();
+/// app.update();
+/// assert_eq!(app.world().resource::<Gravity>().0, 20.0);
+///
+/// // The plugin doesn't take the changes away from the game
+/// assert!(const_tweaker::drain_changes().iter().any(|change| change == key));
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConstTweakerPlugin;
+
+impl Plugin for ConstTweakerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TweakChanged>()
+            .insert_resource(Tweaks {
+                generation: crate::generation(),
+                changes: change_queue(),
+            })
+            .add_systems(First, send_changes)
+            .add_systems(Last, stop_on_exit);
+    }
+}
+
+/// Sent in the `First` schedule for every value that changed since the previous frame, from the
+/// web interface or anywhere else.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct TweakChanged {
+    /// The full path of the constant, e.g. `my_game::physics::GRAVITY`.
+    pub key: String,
+}
+
+/// Access to the tweakable constants from systems.
+///
+/// The resource is marked as changed in every frame in which any value changed, so systems can
+/// use `Res<Tweaks>::is_changed` when they don't care which value it was.
+#[derive(Resource, Debug)]
+pub struct Tweaks {
+    /// The generation of the last change an event was sent for.
+    generation: u64,
+    /// The keys that changed since the previous frame, separate from
+    /// [`drain_changes`](crate::drain_changes) so the game can still use that.
+    changes: Arc<Mutex<Vec<String>>>,
+}
+
+impl Tweaks {
+    /// Get the value of a constant by it's full path, see [`get`](crate::get).
+    pub fn get<T>(&self, key: &str) -> Result<T, Error>
+    where
+        T: TryFrom<Value, Error = Value>,
+    {
+        api::get(key)
+    }
+
+    /// Set the value of a constant by it's full path, see [`set`](crate::set).
+    pub fn set<T>(&mut self, key: &str, value: T) -> Result<(), Error>
+    where
+        T: Into<Value>,
+    {
+        api::set(key, value)
+    }
+
+    /// Set the value of a constant back to the value as declared in the source.
+    pub fn reset(&mut self, key: &str) -> Result<(), Error> {
+        api::reset(key)
+    }
+
+    /// Describe all tweakable constants, see [`iter`](crate::iter).
+    pub fn iter(&self) -> impl Iterator<Item = Descriptor> {
+        api::iter()
+    }
+
+    /// The generation of the last change an event was sent for, see
+    /// [`generation`](crate::generation).
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// Send an event for every value that changed since the previous frame.
+fn send_changes(mut tweaks: ResMut<'_, Tweaks>, mut events: EventWriter<'_, TweakChanged>) {
+    // Only lock through `Deref`, so the resource isn't marked as changed every frame
+    let changes = std::mem::take(&mut *tweaks.changes.lock().unwrap());
+    if changes.is_empty() {
+        return;
+    }
+
+    tweaks.generation = crate::generation();
+    events.write_batch(changes.into_iter().map(|key| TweakChanged { key }));
+}
+
+/// Stop the web server when the app exits, so the port is free for the next process.
+///
+/// The server is stopped for good, even when there are other apps with the plugin.
+fn stop_on_exit(mut exits: EventReader<'_, '_, AppExit>) {
+    if exits.read().next().is_some() {
+        stop_server();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_history, test_field};
+
+    #[test]
+    fn event_for_change() {
+        let _history = lock_history();
+        test_field("bevy::tests::CHANGED", None);
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, ConstTweakerPlugin));
+        app.update();
+
+        api::set("bevy::tests::CHANGED", 2.0).unwrap();
+        app.update();
+
+        // Other tests change values at the same time
+        let events = app.world().resource::<Events<TweakChanged>>();
+        let changed = events
+            .get_cursor()
+            .read(events)
+            .filter(|event| event.key == "bevy::tests::CHANGED")
+            .count();
+        assert_eq!(changed, 1);
+    }
+}
@@ -0,0 +1,191 @@
+//! Forward a local TCP port to the Unix domain socket of the `const` tweaker web server, so it can
+//! be opened in a browser.
+//!
+//! ```sh
+//! const-tweaker-proxy [--insecure] <socket path> [address, default 127.0.0.1:9938]
+//! ```
+//!
+//! Every local user can connect to the port, so the proxy refuses to start when the application
+//! doesn't require a token, unless `--insecure` is passed.
+
+#[cfg(unix)]
+use std::{
+    env,
+    io::{self, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process, thread,
+};
+
+#[cfg(unix)]
+const USAGE: &str =
+    "Usage: const-tweaker-proxy [--insecure] <socket path> [address, default 127.0.0.1:9938]";
+
+/// Copy the data in both directions until either side closes the connection.
+#[cfg(unix)]
+fn forward(tcp: TcpStream, unix: UnixStream) -> io::Result<()> {
+    let (mut tcp_read, mut tcp_write) = (tcp.try_clone()?, tcp);
+    let (mut unix_read, mut unix_write) = (unix.try_clone()?, unix);
+
+    let upload = thread::spawn(move || {
+        let _ = io::copy(&mut tcp_read, &mut unix_write);
+        let _ = unix_write.shutdown(Shutdown::Write);
+    });
+    let _ = io::copy(&mut unix_read, &mut tcp_write);
+    let _ = tcp_write.shutdown(Shutdown::Write);
+    let _ = upload.join();
+
+    Ok(())
+}
+
+/// Whether the application behind the socket rejects requests without the token.
+#[cfg(unix)]
+fn requires_token(socket: &Path) -> io::Result<bool> {
+    let mut unix = UnixStream::connect(socket)?;
+    unix.write_all(b"GET /should_refresh HTTP/1.0\r\nHost: localhost\r\n\r\n")?;
+
+    let mut response = String::new();
+    unix.read_to_string(&mut response)?;
+
+    Ok(response.split_whitespace().nth(1) == Some("401"))
+}
+
+#[cfg(unix)]
+fn main() {
+    let mut insecure = false;
+    let mut positional = vec![];
+    for arg in env::args().skip(1) {
+        match &*arg {
+            "--insecure" => insecure = true,
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let socket = match positional.next() {
+        Some(socket) => PathBuf::from(socket),
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let address = positional
+        .next()
+        .unwrap_or_else(|| "127.0.0.1:9938".to_string());
+
+    match requires_token(&socket) {
+        Ok(true) => (),
+        Ok(false) if insecure => eprintln!(
+            "WARNING: the application doesn't require a token, every user on this machine can \
+             change its values through {}",
+            address
+        ),
+        Ok(false) => {
+            eprintln!(
+                "The application doesn't require a token, so every user on this machine could \
+                 change its values through {}.\nStart it with CONST_TWEAKER_AUTH=1 or pass \
+                 --insecure to forward the port anyway.",
+                address
+            );
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!(
+                "Could not connect to {}, is the application running? {}",
+                socket.to_string_lossy(),
+                err
+            );
+            process::exit(1);
+        }
+    }
+
+    let listener = TcpListener::bind(&address).unwrap_or_else(|err| {
+        eprintln!("Could not listen on {}: {}", address, err);
+        process::exit(1);
+    });
+    println!(
+        "Forwarding http://{} to {}",
+        address,
+        socket.to_string_lossy()
+    );
+
+    for tcp in listener.incoming() {
+        let tcp = match tcp {
+            Ok(tcp) => tcp,
+            Err(err) => {
+                eprintln!("Could not accept connection: {}", err);
+                continue;
+            }
+        };
+
+        let socket = socket.clone();
+        thread::spawn(move || {
+            let result = UnixStream::connect(&socket).and_then(|unix| forward(tcp, unix));
+            if let Err(err) = result {
+                eprintln!("Could not connect to {}: {}", socket.to_string_lossy(), err);
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("Unix domain sockets are not supported on this platform");
+    std::process::exit(1);
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{fs, os::unix::net::UnixListener};
+
+    /// Answer a single request on a new socket with the response, returns the path of the socket.
+    fn serve(name: &str, response: &'static str) -> (PathBuf, thread::JoinHandle<()>) {
+        let path = env::temp_dir().join(format!(
+            "const-tweaker-proxy-{}-{}.sock",
+            process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // The request has no body, so it ends with the empty line
+            let mut request = vec![];
+            let mut byte = [0];
+            while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                request.push(byte[0]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        (path, server)
+    }
+
+    #[test]
+    fn token_required() {
+        let (path, server) = serve("required", "HTTP/1.0 401 Unauthorized\r\n\r\n");
+
+        assert!(requires_token(&path).unwrap());
+        server.join().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn token_not_required() {
+        let (path, server) = serve("not_required", "HTTP/1.0 200 OK\r\n\r\nrefresh");
+
+        assert!(!requires_token(&path).unwrap());
+        server.join().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn not_running() {
+        let path = env::temp_dir().join("const-tweaker-proxy-does-not-exist.sock");
+
+        assert!(requires_token(&path).is_err());
+    }
+}
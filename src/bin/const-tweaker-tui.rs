@@ -0,0 +1,422 @@
+//! Terminal interface for the web server of a running application using `const_tweaker`, for
+//! tweaking values over SSH without a browser.
+
+#[path = "shared/client.rs"]
+mod client;
+
+use client::{text, Client};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use serde_json::Value;
+use std::{
+    env, io, process,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str = "Usage: const-tweaker-tui [--address <address>] [--token <token>]
+
+Keys:
+    Up/Down          Select a value
+    Left/Right       Decrease/increase the value by a step, toggle booleans
+    PageUp/PageDown  Increase/decrease the value by 10 steps
+    Enter            Type a value
+    r                Reset the value to the value from the source
+    q/Esc            Quit
+
+Options:
+    -a, --address <address>  Address of the application, `unix:<path>` for a Unix domain socket,
+                             defaults to CONST_TWEAKER_ADDRESS or 127.0.0.1:9938
+    -t, --token <token>      Authentication token, defaults to CONST_TWEAKER_TOKEN";
+
+/// How often the values are fetched to show changes made somewhere else.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Width of the bar showing where the value is in the range.
+const BAR_WIDTH: usize = 20;
+
+/// A line in the list.
+enum Row {
+    /// A part of the module path, indented by its depth.
+    Module { name: String, depth: usize },
+    /// A field by its index.
+    Field { index: usize, depth: usize },
+}
+
+/// The state of the interface.
+struct App {
+    client: Client,
+    /// All fields with their metadata, sorted by module.
+    fields: Vec<Value>,
+    /// The generation of the values when they were last fetched.
+    generation: u64,
+    /// The module tree with the fields.
+    rows: Vec<Row>,
+    list: ListState,
+    /// The text typed for the selected value, when editing it.
+    input: Option<String>,
+    /// The result of the last action.
+    status: String,
+}
+
+impl App {
+    /// Fetch all fields and select the first one.
+    fn new(client: Client) -> Result<Self, String> {
+        let mut app = App {
+            client,
+            fields: vec![],
+            generation: 0,
+            rows: vec![],
+            list: ListState::default(),
+            input: None,
+            status: String::new(),
+        };
+        app.refresh()?;
+        app.select(0, 1);
+
+        Ok(app)
+    }
+
+    /// Fetch the fields again when any value changed, or when new constants are registered.
+    fn refresh(&mut self) -> Result<(), String> {
+        let (generation, values) = self.client.values()?;
+        if generation == self.generation
+            && values.len() == self.fields.len()
+            && !self.rows.is_empty()
+        {
+            return Ok(());
+        }
+
+        let selected = self.selected().map(|field| field["key"].clone());
+        self.fields = self.client.fields()?;
+        self.generation = generation;
+        self.rows = tree(&self.fields);
+
+        // Keep the same field selected
+        if let Some(selected) = selected {
+            let row = self.rows.iter().position(|row| match row {
+                Row::Field { index, .. } => self.fields[*index]["key"] == selected,
+                Row::Module { .. } => false,
+            });
+            self.list.select(row);
+        }
+
+        Ok(())
+    }
+
+    /// The selected field.
+    fn selected(&self) -> Option<&Value> {
+        match self.rows.get(self.list.selected()?)? {
+            Row::Field { index, .. } => self.fields.get(*index),
+            Row::Module { .. } => None,
+        }
+    }
+
+    /// Select the first field from the row in the direction, skipping the module names.
+    fn select(&mut self, from: usize, direction: isize) {
+        let mut row = from as isize;
+        while row >= 0 && (row as usize) < self.rows.len() {
+            if let Row::Field { .. } = self.rows[row as usize] {
+                self.list.select(Some(row as usize));
+                return;
+            }
+            row += direction;
+        }
+    }
+
+    /// Handle a key press, returns whether to quit.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if let Some(input) = &mut self.input {
+            match code {
+                KeyCode::Enter => {
+                    let input = self.input.take().unwrap_or_default();
+                    self.set_text(input);
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(character) => input.push(character),
+                _ => (),
+            }
+
+            return false;
+        }
+
+        let current = self.list.selected().unwrap_or(0);
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Up => self.select(current.saturating_sub(1), -1),
+            KeyCode::Down => self.select(current + 1, 1),
+            KeyCode::Left => self.step(-1),
+            KeyCode::Right => self.step(1),
+            KeyCode::PageUp => self.step(10),
+            KeyCode::PageDown => self.step(-10),
+            KeyCode::Enter => self.input = self.selected().map(|field| text(&field["value"])),
+            KeyCode::Char('r') => {
+                if let Some(key) = self.selected().map(|field| text(&field["key"])) {
+                    self.status = match self.client.reset(&key) {
+                        Ok(()) => format!("{} is reset", key),
+                        Err(err) => err,
+                    };
+                }
+            }
+            _ => (),
+        }
+
+        false
+    }
+
+    /// Change the selected value by a number of steps within the range, or toggle it.
+    fn step(&mut self, steps: i64) {
+        let field = match self.selected() {
+            Some(field) => field,
+            None => return,
+        };
+
+        let text = match (&field["value"], &field["step"]) {
+            (Value::Bool(value), _) => (!value).to_string(),
+            (Value::Number(value), Value::Number(step)) => {
+                let (min, max) = (&field["min"], &field["max"]);
+                if value.is_f64() || step.is_f64() {
+                    let step = step.as_f64().unwrap_or(1.0);
+                    let new = value.as_f64().unwrap_or(0.0) + step * steps as f64;
+                    let new = new
+                        .max(min.as_f64().unwrap_or(f64::MIN))
+                        .min(max.as_f64().unwrap_or(f64::MAX));
+
+                    // Round to the precision of the step to get rid of floating point errors
+                    format!("{:.*}", decimals(step), new)
+                } else {
+                    let as_integer = |number: &Value| number.to_string().parse::<i128>().ok();
+                    let step = as_integer(&field["step"]).unwrap_or(1);
+                    let new = as_integer(&field["value"]).unwrap_or(0) + step * steps as i128;
+                    let new = new
+                        .max(as_integer(min).unwrap_or(i128::MIN))
+                        .min(as_integer(max).unwrap_or(i128::MAX));
+
+                    new.to_string()
+                }
+            }
+            _ => {
+                self.status = "Press enter to type a value".to_string();
+                return;
+            }
+        };
+
+        self.set_text(text);
+    }
+
+    /// Send the value as text to the server, which parses it.
+    fn set_text(&mut self, text: String) {
+        let key = match self.selected() {
+            Some(field) => client::text(&field["key"]),
+            None => return,
+        };
+
+        self.status = match self.client.set(&key, &text) {
+            Ok(value) => format!("{} = {}", key, client::text(&value)),
+            Err(err) => err,
+        };
+    }
+
+    /// Draw the module tree, the status & the help.
+    fn render(&mut self, frame: &mut Frame<'_>) {
+        let [list_area, status_area, help_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let name_width = self
+            .fields
+            .iter()
+            .map(|field| name(field).len())
+            .max()
+            .unwrap_or(0);
+        let items = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Module { name, depth } => ListItem::new(Line::from(vec![
+                    Span::raw("  ".repeat(*depth)),
+                    Span::styled(
+                        format!("{}::", name),
+                        Style::new().add_modifier(Modifier::BOLD),
+                    ),
+                ])),
+                Row::Field { index, depth } => {
+                    let field = &self.fields[*index];
+                    let modified = field["modified"].as_bool().unwrap_or(false);
+
+                    ListItem::new(Line::from(vec![
+                        Span::raw("  ".repeat(*depth)),
+                        Span::raw(format!("{:width$}  ", name(field), width = name_width)),
+                        Span::raw(bar(field)),
+                        if modified {
+                            Span::raw(text(&field["value"])).yellow()
+                        } else {
+                            Span::raw(text(&field["value"]))
+                        },
+                        Span::raw(format!("  {}", text(&field["type"]))).dark_gray(),
+                    ]))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let list = List::new(items)
+            .block(Block::bordered().title(" const-tweaker "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        let status = match &self.input {
+            Some(input) => format!("New value: {}_", input),
+            None => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+        frame.render_widget(
+            Paragraph::new("↑↓ select  ←→ step  PgUp/PgDn 10 steps  Enter type  r reset  q quit")
+                .dark_gray(),
+            help_area,
+        );
+    }
+}
+
+/// Build the module tree, showing only the parts of the module path that differ from the
+/// previous module.
+fn tree(fields: &[Value]) -> Vec<Row> {
+    let mut rows = vec![];
+    let mut previous: Vec<&str> = vec![];
+
+    for (index, field) in fields.iter().enumerate() {
+        let module = field["module"]
+            .as_str()
+            .unwrap_or_default()
+            .split("::")
+            .collect::<Vec<_>>();
+        let shared = previous
+            .iter()
+            .zip(module.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        for (depth, name) in module.iter().enumerate().skip(shared) {
+            rows.push(Row::Module {
+                name: name.to_string(),
+                depth,
+            });
+        }
+        rows.push(Row::Field {
+            index,
+            depth: module.len(),
+        });
+
+        previous = module;
+    }
+
+    rows
+}
+
+/// The name of the constant without the module.
+fn name(field: &Value) -> &str {
+    field["key"]
+        .as_str()
+        .and_then(|key| key.rsplit("::").next())
+        .unwrap_or_default()
+}
+
+/// A bar showing where the value is between the minimum and the maximum.
+fn bar(field: &Value) -> String {
+    let (value, min, max) = match (
+        field["value"].as_f64(),
+        field["min"].as_f64(),
+        field["max"].as_f64(),
+    ) {
+        (Some(value), Some(min), Some(max)) if max > min => (value, min, max),
+        _ => return " ".repeat(BAR_WIDTH + 3),
+    };
+
+    let filled =
+        (((value - min) / (max - min)).clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as usize;
+
+    format!(
+        "[{}{}] ",
+        "=".repeat(filled),
+        " ".repeat(BAR_WIDTH - filled)
+    )
+}
+
+/// The amount of decimals of the step.
+fn decimals(step: f64) -> usize {
+    step.to_string()
+        .split_once('.')
+        .map(|(_, decimals)| decimals.len())
+        .unwrap_or(0)
+}
+
+/// Draw the interface & handle the keys until quitting.
+fn run(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    let mut last_refresh = Instant::now();
+
+    loop {
+        terminal.draw(|frame| app.render(frame))?;
+
+        if event::poll(REFRESH_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && app.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+
+        if last_refresh.elapsed() >= REFRESH_INTERVAL || app.generation == 0 {
+            last_refresh = Instant::now();
+            if let Err(err) = app.refresh() {
+                app.status = err;
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut client = Client::from_env();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match &*arg {
+            "-a" | "--address" | "-t" | "--token" => args.next(),
+            _ => None,
+        };
+        match (&*arg, value) {
+            ("-a" | "--address", Some(address)) => client.address = address,
+            ("-t" | "--token", Some(token)) => client.token = Some(token),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    let mut app = match App::new(client) {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app);
+    ratatui::restore();
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Command line client for the web server of a running application using `const_tweaker`.
+
+#[path = "shared/client.rs"]
+mod client;
+
+use client::{text, Client};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, Read, Write},
+    process, thread,
+    time::Duration,
+};
+
+const USAGE: &str =
+    "Usage: const-tweaker [--address <address>] [--token <token>] [--json] <command>
+
+Commands:
+    list                 Show all values with their metadata
+    get <key>            Show a single value
+    set <key> <value>    Change a value
+    watch                Print values every time they change
+    export               Print all values as JSON
+    import [file]        Set the values from a JSON file as written by export, or from stdin
+
+Options:
+    -a, --address <address>  Address of the application, `unix:<path>` for a Unix domain socket,
+                             defaults to CONST_TWEAKER_ADDRESS or 127.0.0.1:9938
+    -t, --token <token>      Authentication token, defaults to CONST_TWEAKER_TOKEN
+    -j, --json               Print JSON instead of a table";
+
+/// How often the values are fetched when watching them.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The parsed command line arguments.
+struct Args {
+    client: Client,
+    json: bool,
+    command: Vec<String>,
+}
+
+impl Args {
+    /// Parse the command line arguments without the name of the binary, using the environment
+    /// variables as defaults.
+    fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut client = Client::from_env();
+        let mut json = false;
+        let mut command = vec![];
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match &*arg {
+                "-a" | "--address" => client.address = args.next().ok_or("missing address")?,
+                "-t" | "--token" => client.token = Some(args.next().ok_or("missing token")?),
+                "-j" | "--json" => json = true,
+                "-h" | "--help" => return Err(String::new()),
+                _ if arg.starts_with('-') && command.is_empty() => {
+                    return Err(format!("unknown option {}", arg))
+                }
+                _ => command.push(arg),
+            }
+        }
+
+        Ok(Args {
+            client,
+            json,
+            command,
+        })
+    }
+}
+
+fn main() {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            if !err.is_empty() {
+                eprintln!("error: {}\n", err);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let command = args.command.iter().map(String::as_str).collect::<Vec<_>>();
+    let result = match command.as_slice() {
+        ["list"] => list(&args),
+        ["get", key] => get(&args, key),
+        ["set", key, value] => set(&args, key, value),
+        ["watch"] => watch(&args),
+        ["export"] => export(&args),
+        ["import"] => import(&args, None),
+        ["import", file] => import(&args, Some(file)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+/// Show all values with their metadata.
+fn list(args: &Args) -> Result<(), String> {
+    let fields = args.client.fields()?;
+
+    if args.json {
+        print_json(&Value::Array(fields));
+    } else {
+        let rows = fields
+            .iter()
+            .map(|field| {
+                let range = match (&field["min"], &field["max"]) {
+                    (Value::Null, _) | (_, Value::Null) => String::new(),
+                    (min, max) => format!("{}..{}", min, max),
+                };
+
+                vec![
+                    text(&field["key"]),
+                    text(&field["type"]),
+                    text(&field["value"]),
+                    text(&field["default"]),
+                    range,
+                ]
+            })
+            .collect::<Vec<_>>();
+        print_table(&["KEY", "TYPE", "VALUE", "DEFAULT", "RANGE"], &rows);
+    }
+
+    Ok(())
+}
+
+/// Show a single value.
+fn get(args: &Args, key: &str) -> Result<(), String> {
+    let field = args
+        .client
+        .fields()?
+        .into_iter()
+        .find(|field| field["key"] == key)
+        .ok_or_else(|| format!("no tweakable constant named \"{}\"", key))?;
+
+    if args.json {
+        print_json(&field);
+    } else {
+        println!("{}", text(&field["value"]));
+    }
+
+    Ok(())
+}
+
+/// Change a value.
+fn set(args: &Args, key: &str, value: &str) -> Result<(), String> {
+    let value = args.client.set(key, value)?;
+
+    if args.json {
+        print_json(&json!({ "key": key, "value": value }));
+    } else {
+        println!("{} = {}", key, text(&value));
+    }
+
+    Ok(())
+}
+
+/// Print the values every time they change.
+fn watch(args: &Args) -> Result<(), String> {
+    let (mut last_generation, mut last_values) = args.client.values()?;
+
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+
+        let (generation, values) = args.client.values()?;
+        if generation == last_generation {
+            continue;
+        }
+
+        for (key, value) in values.iter() {
+            if last_values.get(key) == Some(value) {
+                continue;
+            }
+
+            if args.json {
+                println!("{}", json!({ "key": key, "value": value }));
+            } else {
+                println!("{} = {}", key, text(value));
+            }
+        }
+        // Make sure the output is visible when piped
+        io::stdout().flush().map_err(|err| err.to_string())?;
+
+        last_generation = generation;
+        last_values = values;
+    }
+}
+
+/// Print all values as a JSON object, the output can be used by `import`.
+fn export(args: &Args) -> Result<(), String> {
+    let (_, values) = args.client.values()?;
+
+    print_json(&Value::Object(values.into_iter().collect::<Map<_, _>>()));
+
+    Ok(())
+}
+
+/// Set all values from a JSON object, continuing when a value is rejected.
+fn import(args: &Args, file: Option<&str>) -> Result<(), String> {
+    let contents = match file {
+        Some(file) => {
+            fs::read_to_string(file).map_err(|err| format!("could not read {}: {}", file, err))?
+        }
+        None => {
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .map_err(|err| format!("could not read stdin: {}", err))?;
+
+            contents
+        }
+    };
+    let values: BTreeMap<String, Value> =
+        serde_json::from_str(&contents).map_err(|err| format!("invalid JSON: {}", err))?;
+
+    let mut failed = 0;
+    for (key, value) in values {
+        match args.client.set(&key, &text(&value)) {
+            Ok(value) if args.json => println!("{}", json!({ "key": key, "value": value })),
+            Ok(value) => println!("{} = {}", key, text(&value)),
+            Err(err) => {
+                eprintln!("error: {}: {}", key, err);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} values could not be set", failed))
+    }
+}
+
+/// Print a JSON value so it's readable.
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("Could not encode JSON")
+    );
+}
+
+/// Print the rows with aligned columns.
+fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let widths = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain(std::iter::once(header[column].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let print_row = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+
+    print_row(header.to_vec());
+    for row in rows {
+        print_row(row.iter().map(String::as_str).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse the arguments as if they were passed after the name of the binary.
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn command() {
+        let args = parse(&["set", "game::SPEED", "2.5"]).unwrap();
+
+        assert_eq!(args.command, ["set", "game::SPEED", "2.5"]);
+        assert!(!args.json);
+    }
+
+    #[test]
+    fn options() {
+        let args = parse(&[
+            "-a",
+            "unix:/tmp/game.sock",
+            "--token",
+            "secret",
+            "-j",
+            "list",
+        ])
+        .unwrap();
+
+        assert_eq!(args.client.address, "unix:/tmp/game.sock");
+        assert_eq!(args.client.token.as_deref(), Some("secret"));
+        assert!(args.json);
+        assert_eq!(args.command, ["list"]);
+    }
+
+    #[test]
+    fn options_after_the_command() {
+        // Negative numbers are values, not options
+        let args = parse(&["set", "game::SPEED", "-1", "--json"]).unwrap();
+
+        assert_eq!(args.command, ["set", "game::SPEED", "-1"]);
+        assert!(args.json);
+    }
+
+    #[test]
+    fn missing_values() {
+        assert_eq!(
+            parse(&["list", "--address"]).err().unwrap(),
+            "missing address"
+        );
+        assert_eq!(parse(&["-t"]).err().unwrap(), "missing token");
+    }
+
+    #[test]
+    fn unknown_option() {
+        assert_eq!(
+            parse(&["--verbose", "list"]).err().unwrap(),
+            "unknown option --verbose"
+        );
+    }
+
+    #[test]
+    fn help() {
+        // An empty error only prints the usage
+        assert_eq!(parse(&["list", "--help"]).err().unwrap(), "");
+    }
+}
@@ -0,0 +1,316 @@
+//! HTTP client for the web server of a running application, shared by the binaries.
+
+// Not every binary uses every request
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    env,
+    io::{self, Read, Write},
+    net::TcpStream,
+};
+
+/// Connection settings for the web server of the application.
+pub struct Client {
+    /// The address of the server, `unix:<path>` for a Unix domain socket.
+    pub address: String,
+    /// The authentication token.
+    pub token: Option<String>,
+}
+
+impl Client {
+    /// Use the address & token from the environment variables, or the defaults.
+    pub fn from_env() -> Self {
+        Client {
+            address: env::var("CONST_TWEAKER_ADDRESS")
+                .unwrap_or_else(|_| "127.0.0.1:9938".to_string()),
+            token: env::var("CONST_TWEAKER_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+        }
+    }
+
+    /// Do a request, returning the status code and the body.
+    pub fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<(u16, String), String> {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        // The header makes the changes show up as coming from the command line in the history
+        let mut request = format!(
+            "{} {} HTTP/1.0\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nX-Const-Tweaker-Client: cli\r\n",
+            method,
+            path,
+            body.len()
+        );
+        if let Some(token) = &self.token {
+            request += &format!("X-Const-Tweaker-Token: {}\r\n", token);
+        }
+        request += "\r\n";
+        request += &body;
+
+        // HTTP/1.0 makes the server close the connection after the body instead of chunking it
+        let response = self
+            .send(request.as_bytes())
+            .map_err(|err| format!("could not connect to {}: {}", self.address, err))?;
+
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| "invalid response from the server".to_string())?;
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| "invalid response from the server".to_string())?;
+
+        match status {
+            401 => Err(
+                "unauthorized, pass the token printed by the application with --token".to_string(),
+            ),
+            _ => Ok((status, body.to_string())),
+        }
+    }
+
+    /// Send the raw request and read the raw response.
+    fn send(&self, request: &[u8]) -> io::Result<String> {
+        let mut response = String::new();
+
+        match self.address.strip_prefix("unix:") {
+            #[cfg(unix)]
+            Some(path) => {
+                let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+                stream.write_all(request)?;
+                stream.read_to_string(&mut response)?;
+            }
+            #[cfg(not(unix))]
+            Some(_) => {
+                return Err(io::Error::other(
+                    "Unix domain sockets are not supported on this platform",
+                ))
+            }
+            None => {
+                let mut stream = TcpStream::connect(&self.address)?;
+                stream.write_all(request)?;
+                stream.read_to_string(&mut response)?;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Get a JSON value.
+    pub fn get(&self, path: &str) -> Result<Value, String> {
+        let (status, body) = self.request("GET", path, None)?;
+        if status != 200 {
+            return Err(body);
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|err| format!("invalid response from the server: {}", err))
+    }
+
+    /// All fields with their metadata.
+    pub fn fields(&self) -> Result<Vec<Value>, String> {
+        serde_json::from_value(self.get("/fields")?).map_err(|err| err.to_string())
+    }
+
+    /// The generation & the values by key.
+    pub fn values(&self) -> Result<(u64, BTreeMap<String, Value>), String> {
+        let response = self.get("/values")?;
+        let generation = response["generation"].as_u64().unwrap_or(0);
+        let values = response["values"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|value| Some((value["key"].as_str()?.to_string(), value["value"].clone())))
+            .collect();
+
+        Ok((generation, values))
+    }
+
+    /// Set a value from text, the server parses it with the type of the constant.
+    pub fn set(&self, key: &str, text: &str) -> Result<Value, String> {
+        let (status, body) = self.request(
+            "POST",
+            "/set/text",
+            Some(json!({ "key": key, "value": text })),
+        )?;
+
+        match status {
+            200 => serde_json::from_str(&body).map_err(|err| err.to_string()),
+            // The reason is sent with the current value
+            400 => Err(serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|error| error["error"].as_str().map(str::to_string))
+                .unwrap_or(body)),
+            _ => Err(body),
+        }
+    }
+
+    /// Set a value back to the value from the source.
+    pub fn reset(&self, key: &str) -> Result<(), String> {
+        match self.request("POST", "/reset", Some(json!({ "key": key })))? {
+            (200, _) => Ok(()),
+            (_, body) => Err(body),
+        }
+    }
+}
+
+/// Display a JSON value without the quotes around strings.
+pub fn text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    /// Answer a single request with the response, returns the client & the received request.
+    fn serve(response: &'static str) -> (Client, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = Client {
+            address: listener.local_addr().unwrap().to_string(),
+            token: None,
+        };
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // The request of the client ends when the body is as long as its length
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .and_then(|length| length.parse().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+
+            String::from_utf8(request).unwrap()
+        });
+
+        (client, server)
+    }
+
+    #[test]
+    fn request_headers() {
+        let (mut client, server) = serve("HTTP/1.0 200 OK\r\n\r\n\"ok\"");
+        client.token = Some("secret".to_string());
+
+        assert_eq!(
+            client.request("POST", "/reset", Some(json!({ "key": "game::SPEED" }))),
+            Ok((200, "\"ok\"".to_string()))
+        );
+
+        let request = server.join().unwrap();
+        assert!(
+            request.starts_with("POST /reset HTTP/1.0\r\n"),
+            "{}",
+            request
+        );
+        assert!(
+            request.contains("X-Const-Tweaker-Client: cli\r\n"),
+            "{}",
+            request
+        );
+        assert!(
+            request.contains("X-Const-Tweaker-Token: secret\r\n"),
+            "{}",
+            request
+        );
+        assert!(
+            request.ends_with("\r\n\r\n{\"key\":\"game::SPEED\"}"),
+            "{}",
+            request
+        );
+    }
+
+    #[test]
+    fn unauthorized() {
+        let (client, server) = serve("HTTP/1.0 401 Unauthorized\r\n\r\nUnauthorized");
+
+        let err = client.get("/values").unwrap_err();
+        assert!(err.starts_with("unauthorized"), "{}", err);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn invalid_response() {
+        let (client, server) = serve("garbage");
+
+        assert_eq!(
+            client.get("/values"),
+            Err("invalid response from the server".to_string())
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn invalid_json() {
+        let (client, server) = serve("HTTP/1.0 200 OK\r\n\r\n{");
+
+        let err = client.get("/values").unwrap_err();
+        assert!(
+            err.starts_with("invalid response from the server: "),
+            "{}",
+            err
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn rejected_value() {
+        let (client, server) =
+            serve("HTTP/1.0 400 Bad Request\r\n\r\n{\"error\":\"too fast\",\"value\":1.0}");
+
+        assert_eq!(client.set("game::SPEED", "11"), Err("too fast".to_string()));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn connection_refused() {
+        // Nothing listens on the port anymore after the listener is dropped
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let client = Client {
+            address: address.clone(),
+            token: None,
+        };
+
+        let err = client.get("/values").unwrap_err();
+        assert!(
+            err.starts_with(&format!("could not connect to {}: ", address)),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn text_without_quotes() {
+        assert_eq!(text(&json!("fast")), "fast");
+        assert_eq!(text(&json!(2.5)), "2.5");
+        assert_eq!(text(&json!(true)), "true");
+    }
+}
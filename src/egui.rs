@@ -0,0 +1,242 @@
+use crate::{api, Descriptor, Origin, Scale, Value};
+use ::egui::{
+    emath::{self, Numeric},
+    CollapsingHeader, Grid, Id, Slider, SliderClamping, TextEdit, Ui,
+};
+use std::convert::TryFrom;
+
+/// Show all tweakable constants in an egui `Ui`, grouped by module.
+///
+/// The values are written to the same store as the web interface, so both stay in sync and
+/// changes made here can be undone from the history.
+///
+/// ```rust
+/// fn update(ctx: &egui::Context) {
+///     egui::Window::new("Tweaks").show(ctx, |ui| {
+///         const_tweaker::egui::show(ui);
+///     });
+/// }
+/// ```
+pub fn show(ui: &mut Ui) {
+    let descriptors = api::iter().collect::<Vec<_>>();
+
+    for module in descriptors.chunk_by(|a, b| a.module == b.module) {
+        let name = &module[0].module;
+
+        CollapsingHeader::new(name)
+            .id_salt(("const_tweaker", name))
+            .default_open(true)
+            .show(ui, |ui| {
+                Grid::new(("const_tweaker_grid", name))
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for descriptor in module {
+                            show_field(ui, descriptor);
+                        }
+                    });
+            });
+    }
+}
+
+/// Show a single constant as a row with the name, the widget & a reset button.
+fn show_field(ui: &mut Ui, descriptor: &Descriptor) {
+    let error_id = Id::new(("const_tweaker_error", &descriptor.key));
+
+    let name = descriptor.key.rsplit("::").next().unwrap_or_default();
+    ui.label(name).on_hover_text(&descriptor.file);
+
+    let new_value = match &descriptor.value {
+        Value::F32(value) => slider(ui, descriptor, *value),
+        Value::F64(value) => slider(ui, descriptor, *value),
+        Value::I8(value) => slider(ui, descriptor, *value),
+        Value::U8(value) => slider(ui, descriptor, *value),
+        Value::I16(value) => slider(ui, descriptor, *value),
+        Value::U16(value) => slider(ui, descriptor, *value),
+        Value::I32(value) => slider(ui, descriptor, *value),
+        Value::U32(value) => slider(ui, descriptor, *value),
+        Value::I64(value) => slider(ui, descriptor, *value),
+        Value::U64(value) => slider(ui, descriptor, *value),
+        Value::Usize(value) => slider(ui, descriptor, *value),
+        Value::Bool(value) => {
+            let mut new_value = *value;
+            ui.checkbox(&mut new_value, "")
+                .changed()
+                .then_some(Value::Bool(new_value))
+        }
+        Value::String(value) => text_edit(ui, descriptor, value),
+    };
+
+    if let Some(new_value) = new_value {
+        let result = api::set_from(&descriptor.key, new_value, Origin::Panel);
+        ui.data_mut(|data| match result {
+            Ok(()) => data.remove::<String>(error_id),
+            Err(err) => data.insert_temp(error_id, err.to_string()),
+        });
+    }
+
+    ui.add_enabled_ui(descriptor.value != descriptor.default, |ui| {
+        let reset = ui
+            .small_button("Reset")
+            .on_hover_text("Reset to the value from the source");
+        if reset.clicked() {
+            // The key comes from the store so it always exists
+            let _ = api::reset(&descriptor.key);
+            ui.data_mut(|data| data.remove::<String>(error_id));
+        }
+    });
+    ui.end_row();
+
+    // Show why the last value was rejected below the widget
+    if let Some(error) = ui.data(|data| data.get_temp::<String>(error_id)) {
+        ui.label("");
+        ui.colored_label(ui.visuals().error_fg_color, error);
+        ui.end_row();
+    }
+}
+
+/// A slider for a number with the range of the descriptor, returns the new value when it's
+/// dragged.
+fn slider<T>(ui: &mut Ui, descriptor: &Descriptor, value: T) -> Option<Value>
+where
+    T: Numeric + Into<Value> + TryFrom<Value, Error = Value>,
+{
+    // The range always has the type of the value for numbers
+    let bound = |bound: &Option<Value>| T::try_from(bound.clone()?).ok();
+    let (min, max, step) = (
+        bound(&descriptor.min)?,
+        bound(&descriptor.max)?,
+        bound(&descriptor.step)?,
+    );
+
+    let mut new_value = value;
+    let clamping = if descriptor.allow_out_of_range {
+        // Values outside of the range can still be typed in after double clicking
+        SliderClamping::Edits
+    } else {
+        SliderClamping::Always
+    };
+
+    let slider = match descriptor.scale {
+        // egui only has a logarithmic mapping, so the power curve is a slider over the position
+        // that shows the value, the same as the web interface does it
+        Scale::Pow(exponent) => {
+            let curve = PowCurve {
+                min: min.to_f64(),
+                max: max.to_f64(),
+                step: step.to_f64(),
+                exponent,
+            };
+
+            let value = &mut new_value;
+            Slider::from_get_set(0.0..=1.0, move |position| {
+                if let Some(position) = position {
+                    *value = T::from_f64(curve.value(position));
+                }
+                curve.position(value.to_f64())
+            })
+            .custom_formatter(move |position, _| {
+                emath::format_with_decimals_in_range(curve.value(position), 0..=6)
+            })
+            .custom_parser(move |text| text.trim().parse().ok().map(|value| curve.position(value)))
+        }
+        _ => Slider::new(&mut new_value, min..=max)
+            .step_by(step.to_f64())
+            .logarithmic(descriptor.scale == Scale::Log)
+            .clamping(clamping),
+    };
+
+    ui.add(slider).changed().then(|| new_value.into())
+}
+
+/// The mapping between the position of a slider with a power curve & the value.
+///
+/// The slider only covers the range, values outside of it can't be typed in.
+#[derive(Debug, Clone, Copy)]
+struct PowCurve {
+    min: f64,
+    max: f64,
+    step: f64,
+    exponent: f64,
+}
+
+impl PowCurve {
+    /// The value at a position between 0 and 1, snapped to the step.
+    fn value(&self, position: f64) -> f64 {
+        let value = self.min + (self.max - self.min) * position.clamp(0.0, 1.0).powf(self.exponent);
+
+        ((value / self.step).round() * self.step).clamp(self.min, self.max)
+    }
+
+    /// The position between 0 and 1 of the value, values outside of the range are put at the
+    /// edges.
+    fn position(&self, value: f64) -> f64 {
+        let position = ((value - self.min) / (self.max - self.min)).powf(1.0 / self.exponent);
+
+        if position.is_nan() {
+            0.0
+        } else {
+            position.clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// A text field for a string, returns the new text every time it's edited.
+fn text_edit(ui: &mut Ui, descriptor: &Descriptor, value: &str) -> Option<Value> {
+    let id = Id::new(("const_tweaker_text", &descriptor.key));
+
+    // Keep the text while editing, otherwise a rejected value would be replaced while typing
+    let mut text = ui
+        .data(|data| data.get_temp::<String>(id))
+        .unwrap_or_else(|| value.to_string());
+    let response = ui.add(TextEdit::singleline(&mut text).id(id));
+
+    if response.has_focus() {
+        ui.data_mut(|data| data.insert_temp(id, text.clone()));
+    } else {
+        ui.data_mut(|data| data.remove::<String>(id));
+    }
+
+    response.changed().then_some(Value::String(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_field, Field, DATA};
+    use ::egui::{CentralPanel, Context, RawInput};
+
+    #[test]
+    fn pow_curve() {
+        let curve = PowCurve {
+            min: 0.0,
+            max: 100.0,
+            step: 1.0,
+            exponent: 2.0,
+        };
+
+        assert_eq!(curve.value(0.0), 0.0);
+        assert_eq!(curve.value(0.5), 25.0);
+        assert_eq!(curve.value(1.0), 100.0);
+        assert_eq!(curve.position(25.0), 0.5);
+        assert_eq!(curve.position(-10.0), 0.0);
+        assert_eq!(curve.position(1000.0), 1.0);
+    }
+
+    #[test]
+    fn render() {
+        test_field("egui::tests::LINEAR", None);
+        test_field("egui::tests::POW", None);
+        if let Field::F64 { scale, .. } = &mut *DATA.get_mut("egui::tests::POW").unwrap() {
+            *scale = Scale::Pow(2.0);
+        }
+
+        let ctx = Context::default();
+        let output = ctx.run(RawInput::default(), |ctx| {
+            CentralPanel::default().show(ctx, show);
+        });
+
+        assert!(!output.shapes.is_empty());
+        // Nothing is changed by showing the panel
+        assert_eq!(api::get::<f64>("egui::tests::POW"), Ok(1.0));
+    }
+}
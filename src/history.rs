@@ -0,0 +1,372 @@
+use crate::{notify, Error, Value, DATA};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+/// How many changes are kept, older changes are dropped.
+const HISTORY_SIZE: usize = 100;
+
+/// Changes from the web interface, the egui panel or an OSC controller to the same value made
+/// within this time are merged, so dragging a slider or a fader can be undone in one step.
+const MERGE_WINDOW: Duration = Duration::from_secs(1);
+
+lazy_static::lazy_static! {
+    /// The changes that can be undone, oldest first.
+    static ref UNDO: Mutex<VecDeque<Change>> = Mutex::new(VecDeque::new());
+    /// The changes that can be redone, last undone last.
+    static ref REDO: Mutex<Vec<Change>> = Mutex::new(vec![]);
+}
+
+/// The id of the last change.
+static LAST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Where a change came from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// Set from the web interface.
+    Web,
+    /// Set from code with [`set`](crate::set).
+    Api,
+    /// Reset to the value as declared in the source.
+    Reset,
+    /// Set by applying a preset.
+    Preset,
+    /// An earlier change reverted from the history.
+    Revert,
+    /// Set from a watched file.
+    File,
+    /// Set from the in-game egui panel.
+    Panel,
+    /// Set from an OSC controller.
+    Osc,
+    /// Set by replaying a recording.
+    Replay,
+    /// Set from the `const-tweaker` command line client or the terminal interface.
+    Cli,
+}
+
+impl Origin {
+    /// Lowercase name, used by the website.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Origin::Web => "web",
+            Origin::Api => "api",
+            Origin::Reset => "reset",
+            Origin::Preset => "preset",
+            Origin::Revert => "revert",
+            Origin::File => "file",
+            Origin::Panel => "panel",
+            Origin::Osc => "osc",
+            Origin::Replay => "replay",
+            Origin::Cli => "cli",
+        }
+    }
+}
+
+/// A single change of a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// Unique id, used to revert the change.
+    pub id: u64,
+    /// The full path of the constant.
+    pub key: String,
+    /// The value before the change.
+    pub old: Value,
+    /// The value after the change.
+    pub new: Value,
+    /// When the change was made.
+    pub timestamp: SystemTime,
+    /// Where the change came from.
+    pub origin: Origin,
+}
+
+/// The changes that can be undone, newest first.
+///
+/// Only the last 100 changes are kept.
+pub fn history() -> Vec<Change> {
+    UNDO.lock().unwrap().iter().rev().cloned().collect()
+}
+
+/// Set the value of the last change back to what it was before.
+///
+/// Returns the key of the value that changed, `None` when there's nothing to undo.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0.0, max = 1.0)]
+/// const VALUE: f64 = 0.0;
+///
+/// # let key = concat!(module_path!(), "::VALUE");
+/// const_tweaker::set(key, 0.5).unwrap();
+/// const_tweaker::undo().unwrap();
+/// assert_eq!(*VALUE, 0.0);
+///
+/// const_tweaker::redo().unwrap();
+/// assert_eq!(*VALUE, 0.5);
+///
+/// // Values declared outside of the range in the source can be undone back to as well
+/// #[const_tweaker::tweak(min = 100, max = 1000)]
+/// const SIZE: u32 = 0;
+///
+/// # let key = concat!(module_path!(), "::SIZE");
+/// const_tweaker::set(key, 500u32).unwrap();
+/// const_tweaker::undo().unwrap();
+/// assert_eq!(*SIZE, 0);
+/// ```
+pub fn undo() -> Result<Option<String>, Error> {
+    let change = match UNDO.lock().unwrap().pop_back() {
+        Some(change) => change,
+        None => return Ok(None),
+    };
+
+    if let Err(err) = apply(&change.key, change.old.clone()) {
+        // Keep it so it can be tried again after the value that blocks it is changed
+        UNDO.lock().unwrap().push_back(change);
+
+        return Err(err);
+    }
+
+    let key = change.key.clone();
+    REDO.lock().unwrap().push(change);
+
+    Ok(Some(key))
+}
+
+/// Apply the last undone change again.
+///
+/// Returns the key of the value that changed, `None` when there's nothing to redo.
+pub fn redo() -> Result<Option<String>, Error> {
+    let change = match REDO.lock().unwrap().pop() {
+        Some(change) => change,
+        None => return Ok(None),
+    };
+
+    if let Err(err) = apply(&change.key, change.new.clone()) {
+        REDO.lock().unwrap().push(change);
+
+        return Err(err);
+    }
+
+    let key = change.key.clone();
+    push(&mut UNDO.lock().unwrap(), change);
+
+    Ok(Some(key))
+}
+
+/// Set the value of a single change from the history back to what it was before.
+///
+/// This is recorded as a new change, so it can be undone itself.
+/// Returns the key of the value that changed.
+pub fn revert(id: u64) -> Result<String, Error> {
+    let change = UNDO
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|change| change.id == id)
+        .cloned()
+        .ok_or(Error::UnknownChange(id))?;
+
+    let old = DATA
+        .get(&*change.key)
+        .map(|field| field.current_value())
+        .ok_or_else(|| Error::UnknownKey(change.key.clone()))?;
+    apply(&change.key, change.old.clone())?;
+    record(&change.key, old, change.old, Origin::Revert);
+
+    Ok(change.key)
+}
+
+/// Add a change to the history, clears the changes that can be redone.
+#[doc(hidden)]
+pub fn record(key: &str, old: Value, new: Value, origin: Origin) {
+    if old.is_same(&new) {
+        return;
+    }
+
+    REDO.lock().unwrap().clear();
+
+    let now = SystemTime::now();
+    let mut undo = UNDO.lock().unwrap();
+
+    // Merge with the previous change when it's the same slider being dragged
+    if let Some(last) = undo.back_mut() {
+        let recent = now
+            .duration_since(last.timestamp)
+            .is_ok_and(|elapsed| elapsed < MERGE_WINDOW);
+        // Not the command line, a script setting a value a few times should be undone step by step
+        let slider = matches!(origin, Origin::Web | Origin::Panel | Origin::Osc);
+        if slider && last.origin == origin && last.key == key && recent {
+            last.new = new;
+            last.timestamp = now;
+            if last.old.is_same(&last.new) {
+                undo.pop_back();
+            }
+
+            return;
+        }
+    }
+
+    let id = LAST_ID.fetch_add(1, Ordering::SeqCst) + 1;
+    push(
+        &mut undo,
+        Change {
+            id,
+            key: key.to_string(),
+            old,
+            new,
+            timestamp: now,
+            origin,
+        },
+    );
+}
+
+/// Add a change to the end of the history, dropping the oldest change when it's full.
+fn push(undo: &mut VecDeque<Change>, change: Change) {
+    if undo.len() == HISTORY_SIZE {
+        undo.pop_front();
+    }
+    undo.push_back(change);
+}
+
+/// Set the value without recording it and notify the subscribers.
+///
+/// The value isn't validated, it was accepted before or it's the value from before a change, like
+/// a value from an override at startup.
+fn apply(key: &str, value: Value) -> Result<(), Error> {
+    DATA.get_mut(key)
+        .ok_or_else(|| Error::UnknownKey(key.to_string()))?
+        .restore_value(value);
+
+    // The field must be unlocked before notifying, so the callbacks can read it
+    notify(key);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::set_from, lock_history, test_field};
+    use std::sync::atomic::AtomicBool;
+
+    fn value(key: &str) -> Value {
+        DATA.get(key).unwrap().current_value()
+    }
+
+    /// The number of changes of the constant in the history.
+    fn changes(key: &str) -> usize {
+        history().iter().filter(|change| change.key == key).count()
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let _history = lock_history();
+        test_field("history::tests::UNDO", None);
+        let key = "history::tests::UNDO";
+
+        crate::set(key, 2.0).unwrap();
+        crate::set(key, 3.0).unwrap();
+        assert_eq!(changes(key), 2);
+
+        assert_eq!(undo().unwrap().as_deref(), Some(key));
+        assert_eq!(value(key), Value::F64(2.0));
+        assert_eq!(undo().unwrap().as_deref(), Some(key));
+        assert_eq!(value(key), Value::F64(1.0));
+        assert_eq!(changes(key), 0);
+
+        assert_eq!(redo().unwrap().as_deref(), Some(key));
+        assert_eq!(value(key), Value::F64(2.0));
+        assert_eq!(redo().unwrap().as_deref(), Some(key));
+        assert_eq!(value(key), Value::F64(3.0));
+        assert_eq!(redo().unwrap(), None);
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let _history = lock_history();
+        test_field("history::tests::CLEARED", None);
+        let key = "history::tests::CLEARED";
+
+        crate::set(key, 2.0).unwrap();
+        undo().unwrap();
+        crate::set(key, 4.0).unwrap();
+
+        assert_eq!(redo().unwrap(), None);
+        assert_eq!(value(key), Value::F64(4.0));
+    }
+
+    #[test]
+    fn merge_window() {
+        let _history = lock_history();
+        test_field("history::tests::DRAGGED", None);
+        test_field("history::tests::SCRIPTED", None);
+
+        // Dragging a slider is a single change
+        for value in [2.0, 3.0, 4.0] {
+            set_from("history::tests::DRAGGED", Value::F64(value), Origin::Web).unwrap();
+        }
+        let dragged = history()
+            .into_iter()
+            .filter(|change| change.key == "history::tests::DRAGGED")
+            .collect::<Vec<_>>();
+        assert_eq!(dragged.len(), 1);
+        assert_eq!(
+            (&dragged[0].old, &dragged[0].new),
+            (&Value::F64(1.0), &Value::F64(4.0))
+        );
+
+        // Dragging it back removes the change
+        set_from("history::tests::DRAGGED", Value::F64(1.0), Origin::Web).unwrap();
+        assert_eq!(changes("history::tests::DRAGGED"), 0);
+
+        // The command line isn't merged
+        for value in [2.0, 3.0] {
+            set_from("history::tests::SCRIPTED", Value::F64(value), Origin::Cli).unwrap();
+        }
+        assert_eq!(changes("history::tests::SCRIPTED"), 2);
+    }
+
+    #[test]
+    fn undo_to_a_rejected_value() {
+        let _history = lock_history();
+        // Rejects the value from before the change once it's changed
+        static STRICT: AtomicBool = AtomicBool::new(false);
+        test_field(
+            "history::tests::REJECTED",
+            Some(|value| {
+                if STRICT.load(Ordering::SeqCst) && *value < 2.0 {
+                    Err("too slow".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        );
+        let key = "history::tests::REJECTED";
+
+        crate::set(key, 3.0).unwrap();
+        STRICT.store(true, Ordering::SeqCst);
+
+        undo().unwrap();
+        assert_eq!(value(key), Value::F64(1.0));
+    }
+
+    #[test]
+    fn revert_change() {
+        let _history = lock_history();
+        test_field("history::tests::REVERTED", None);
+        let key = "history::tests::REVERTED";
+
+        crate::set(key, 2.0).unwrap();
+        let id = history()[0].id;
+        crate::set(key, 3.0).unwrap();
+
+        assert_eq!(revert(id).unwrap(), key);
+        assert_eq!(value(key), Value::F64(1.0));
+        assert_eq!(history()[0].origin, Origin::Revert);
+        assert!(matches!(revert(u64::MAX), Err(Error::UnknownChange(_))));
+    }
+}
@@ -0,0 +1,445 @@
+use crate::{api, Descriptor, Error, Origin, Scale, Value, DATA};
+use std::{
+    convert::TryInto,
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    thread,
+};
+
+/// The largest packet that's accepted, OSC controllers send small packets.
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// An argument of an OSC message.
+#[derive(Debug, Clone, PartialEq)]
+enum Argument {
+    Float(f64),
+    Int(i64),
+    Bool(bool),
+    String(String),
+    /// Arguments without a value, like blobs & nil.
+    Other,
+}
+
+/// A single OSC message.
+#[derive(Debug, Clone, PartialEq)]
+struct Message {
+    address: String,
+    arguments: Vec<Argument>,
+}
+
+/// Listen for OSC messages on a UDP socket and apply them to the constants.
+///
+/// The address of a message is the path of the constant with slashes, e.g. the message
+/// `/game/physics/GRAVITY` sets `game::physics::GRAVITY`. The first argument is the value:
+/// floats are positions of a fader between `0` and `1`, mapped to the range of the slider the same
+/// way as the web interface does, and integers, booleans & strings are used as the value itself.
+///
+/// Returns the address it's listening on, messages that can't be applied are printed to stderr.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0.0, max = 10.0, step = 0.5)]
+/// const VALUE: f64 = 0.0;
+///
+/// # use std::{net::UdpSocket, thread, time::Duration};
+/// let address = const_tweaker::listen_osc("127.0.0.1:0").unwrap();
+///
+/// # let osc_address = concat!(module_path!(), "::VALUE").replace("::", "/");
+/// # let mut packet = format!("/{}\0", osc_address).into_bytes();
+/// # packet.resize((packet.len() + 3) / 4 * 4, 0);
+/// # packet.extend_from_slice(b",f\0\0");
+/// # packet.extend_from_slice(&0.25f32.to_be_bytes());
+/// // Send `/<module>/VALUE 0.25` like a controller would
+/// let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// sender.send_to(&packet, address).unwrap();
+///
+/// # for _ in 0..100 {
+/// #     if *VALUE != 0.0 { break; }
+/// #     thread::sleep(Duration::from_millis(10));
+/// # }
+/// assert_eq!(*VALUE, 2.5);
+/// ```
+pub fn listen_osc<A>(address: A) -> io::Result<SocketAddr>
+where
+    A: ToSocketAddrs,
+{
+    let socket = UdpSocket::bind(address)?;
+    let local_address = socket.local_addr()?;
+
+    thread::spawn(move || {
+        let mut buffer = vec![0; MAX_PACKET_SIZE];
+
+        loop {
+            let size = match socket.recv(&mut buffer) {
+                Ok(size) => size,
+                Err(err) => {
+                    eprintln!("const-tweaker: could not receive OSC packet: {}", err);
+                    continue;
+                }
+            };
+
+            let mut messages = vec![];
+            if let Err(err) = parse_packet(&buffer[..size], &mut messages) {
+                eprintln!("const-tweaker: invalid OSC packet: {}", err);
+                continue;
+            }
+
+            for message in messages {
+                if let Err(err) = apply(&message) {
+                    eprintln!("const-tweaker: {}: {}", message.address, err);
+                }
+            }
+        }
+    });
+
+    Ok(local_address)
+}
+
+/// Set the constant from the address to the first argument.
+fn apply(message: &Message) -> Result<(), String> {
+    let key = message.address.trim_start_matches('/').replace('/', "::");
+    let argument = message.arguments.first().ok_or("message without a value")?;
+
+    let value = {
+        let field = DATA
+            .get(&*key)
+            .ok_or_else(|| Error::UnknownKey(key.clone()).to_string())?;
+        let descriptor = Descriptor::new(&key, &field);
+
+        match (argument, &descriptor.value) {
+            (Argument::Float(position), Value::Bool(_)) => Value::Bool(*position >= 0.5),
+            (Argument::Float(position), _) => {
+                let value = scaled_value(&descriptor, *position)
+                    .ok_or_else(|| format!("a fader can't set {}", descriptor.type_name))?;
+
+                field.value_from_json(descriptor.value.number_json(value))?
+            }
+            (Argument::Int(value), Value::Bool(_)) => Value::Bool(*value != 0),
+            (Argument::Int(value), _) => field.value_from_json((*value).into())?,
+            (Argument::Bool(value), _) => field.value_from_json((*value).into())?,
+            (Argument::String(text), _) => field.value_from_text(text)?,
+            (Argument::Other, _) => return Err("unsupported argument type".to_string()),
+        }
+    };
+
+    api::set_from(&key, value, Origin::Osc).map_err(|err| err.to_string())
+}
+
+/// Map the position of a fader between `0` and `1` to the range of the field, like the sliders on
+/// the website do.
+fn scaled_value(descriptor: &Descriptor, position: f64) -> Option<f64> {
+    let as_f64 = |value: &Option<Value>| value.as_ref()?.to_json().as_f64();
+    let (min, max, step) = (
+        as_f64(&descriptor.min)?,
+        as_f64(&descriptor.max)?,
+        as_f64(&descriptor.step)?,
+    );
+    let position = position.clamp(0.0, 1.0);
+
+    // Convert the position of the fader to the value using the curve
+    let value = match descriptor.scale {
+        Scale::Log => (min.ln() + position * (max.ln() - min.ln())).exp(),
+        scale => min + (max - min) * position.powf(scale.exponent()),
+    };
+
+    // Snap to the step, and get rid of floating point errors introduced by that
+    let value = if step > 0.0 {
+        (value / step).round() * step
+    } else {
+        value
+    };
+    let value = format!("{:.11e}", value).parse().unwrap_or(value);
+
+    Some(value.clamp(min, max))
+}
+
+/// Parse a message or a bundle of messages.
+fn parse_packet(packet: &[u8], messages: &mut Vec<Message>) -> Result<(), String> {
+    if let Some(elements) = packet.strip_prefix(b"#bundle\0") {
+        // Skip the time tag, the messages are applied immediately
+        let mut elements = elements.get(8..).ok_or("bundle without a time tag")?;
+
+        while !elements.is_empty() {
+            let size = read_u32(&mut elements)? as usize;
+            let element = elements.get(..size).ok_or("bundle element is too short")?;
+            parse_packet(element, messages)?;
+            elements = &elements[size..];
+        }
+
+        return Ok(());
+    }
+
+    let mut data = packet;
+    let address = read_string(&mut data)?;
+    if !address.starts_with('/') {
+        return Err(format!("invalid address \"{}\"", address));
+    }
+
+    // Old implementations may leave out the type tags, which means there are no arguments
+    let type_tags = if data.is_empty() {
+        String::new()
+    } else {
+        read_string(&mut data)?
+    };
+    let mut arguments = vec![];
+    for tag in type_tags.chars().skip_while(|tag| *tag == ',') {
+        let argument = match tag {
+            'f' => Argument::Float(f32::from_bits(read_u32(&mut data)?) as f64),
+            'd' => Argument::Float(f64::from_bits(read_u64(&mut data)?)),
+            'i' => Argument::Int(read_u32(&mut data)? as i32 as i64),
+            'h' => Argument::Int(read_u64(&mut data)? as i64),
+            's' | 'S' => Argument::String(read_string(&mut data)?),
+            'T' => Argument::Bool(true),
+            'F' => Argument::Bool(false),
+            'N' | 'I' | '[' | ']' => Argument::Other,
+            'c' | 'r' | 'm' => {
+                read_u32(&mut data)?;
+                Argument::Other
+            }
+            't' => {
+                read_u64(&mut data)?;
+                Argument::Other
+            }
+            'b' => {
+                let size = read_u32(&mut data)? as usize;
+                data = data.get(padded(size)..).ok_or("blob is too short")?;
+                Argument::Other
+            }
+            tag => return Err(format!("unknown type tag '{}'", tag)),
+        };
+        arguments.push(argument);
+    }
+
+    messages.push(Message { address, arguments });
+
+    Ok(())
+}
+
+/// Read a null terminated string padded to 4 bytes.
+fn read_string(data: &mut &[u8]) -> Result<String, String> {
+    let end = data
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or("string without a terminator")?;
+    let string = String::from_utf8(data[..end].to_vec()).map_err(|err| err.to_string())?;
+    *data = data.get(padded(end + 1)..).unwrap_or_default();
+
+    Ok(string)
+}
+
+/// Read a big endian 32 bit number.
+fn read_u32(data: &mut &[u8]) -> Result<u32, String> {
+    let bytes = data.get(..4).ok_or("message is too short")?;
+    *data = &data[4..];
+
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+/// Read a big endian 64 bit number.
+fn read_u64(data: &mut &[u8]) -> Result<u64, String> {
+    let bytes = data.get(..8).ok_or("message is too short")?;
+    *data = &data[8..];
+
+    Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+/// Round the size up to a multiple of 4 bytes, everything in OSC is aligned to that.
+fn padded(size: usize) -> usize {
+    size.div_ceil(4) * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a string with its terminator, padded to 4 bytes.
+    fn string(string: &str) -> Vec<u8> {
+        let mut bytes = string.as_bytes().to_vec();
+        bytes.resize(padded(string.len() + 1), 0);
+
+        bytes
+    }
+
+    /// Encode a message from the address, the type tags & the encoded arguments.
+    fn message(address: &str, type_tags: &str, arguments: &[u8]) -> Vec<u8> {
+        [string(address), string(type_tags), arguments.to_vec()].concat()
+    }
+
+    /// Encode a bundle of the encoded elements.
+    fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = b"#bundle\0".to_vec();
+        // The time tag for "immediately"
+        bytes.extend_from_slice(&1u64.to_be_bytes());
+        for element in elements {
+            bytes.extend_from_slice(&(element.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(element);
+        }
+
+        bytes
+    }
+
+    /// Parse a packet into a new list of messages.
+    fn parse(packet: &[u8]) -> Result<Vec<Message>, String> {
+        let mut messages = vec![];
+        parse_packet(packet, &mut messages)?;
+
+        Ok(messages)
+    }
+
+    #[test]
+    fn float() {
+        let messages = parse(&message("/game/SPEED", ",f", &0.5f32.to_be_bytes())).unwrap();
+
+        assert_eq!(
+            messages,
+            [Message {
+                address: "/game/SPEED".to_string(),
+                arguments: vec![Argument::Float(0.5)],
+            }]
+        );
+    }
+
+    #[test]
+    fn all_types() {
+        let arguments = [
+            &2.5f64.to_be_bytes()[..],
+            &(-3i32).to_be_bytes(),
+            &(-4i64).to_be_bytes(),
+            &string("fast"),
+            &string("symbol"),
+            &3u32.to_be_bytes(),
+            // The blob is padded to 4 bytes
+            &[1, 2, 3, 0],
+            &0u32.to_be_bytes(),
+            &1u64.to_be_bytes(),
+        ]
+        .concat();
+        let messages = parse(&message("/game/ALL", ",dihsSTFNIbct", &arguments)).unwrap();
+
+        assert_eq!(
+            messages[0].arguments,
+            [
+                Argument::Float(2.5),
+                Argument::Int(-3),
+                Argument::Int(-4),
+                Argument::String("fast".to_string()),
+                Argument::String("symbol".to_string()),
+                Argument::Bool(true),
+                Argument::Bool(false),
+                Argument::Other,
+                Argument::Other,
+                Argument::Other,
+                Argument::Other,
+                Argument::Other,
+            ]
+        );
+    }
+
+    #[test]
+    fn without_type_tags() {
+        let messages = parse(&string("/game/RESET")).unwrap();
+
+        assert_eq!(messages[0].address, "/game/RESET");
+        assert!(messages[0].arguments.is_empty());
+    }
+
+    #[test]
+    fn padding() {
+        // The terminator of an address of 3 characters fits in 4 bytes, one of 4 characters
+        // needs another 4 bytes
+        for address in ["/ab", "/abc", "/abcd"] {
+            let messages = parse(&message(address, ",i", &7i32.to_be_bytes())).unwrap();
+
+            assert_eq!(messages[0].address, address);
+            assert_eq!(messages[0].arguments, [Argument::Int(7)]);
+        }
+        assert_eq!(string("/ab").len(), 4);
+        assert_eq!(string("/abc").len(), 8);
+    }
+
+    #[test]
+    fn bundles() {
+        let packet = bundle(&[
+            message("/game/SPEED", ",f", &0.5f32.to_be_bytes()),
+            // Bundles can be nested
+            bundle(&[message("/game/DEBUG", ",T", &[])]),
+        ]);
+        let messages = parse(&packet).unwrap();
+
+        let addresses = messages
+            .iter()
+            .map(|message| &*message.address)
+            .collect::<Vec<_>>();
+        assert_eq!(addresses, ["/game/SPEED", "/game/DEBUG"]);
+    }
+
+    #[test]
+    fn empty_bundle() {
+        assert_eq!(parse(&bundle(&[])).unwrap(), []);
+    }
+
+    #[test]
+    fn truncated_bundle() {
+        assert_eq!(
+            parse(b"#bundle\0\0\0\0\0"),
+            Err("bundle without a time tag".to_string())
+        );
+
+        let mut packet = bundle(&[message("/game/SPEED", ",f", &0.5f32.to_be_bytes())]);
+        packet.truncate(packet.len() - 1);
+        assert_eq!(
+            parse(&packet),
+            Err("bundle element is too short".to_string())
+        );
+
+        // Not even the size of the next element fits
+        let mut packet = bundle(&[]);
+        packet.extend_from_slice(&[0, 0]);
+        assert_eq!(parse(&packet), Err("message is too short".to_string()));
+    }
+
+    #[test]
+    fn truncated_arguments() {
+        assert_eq!(
+            parse(&message("/game/SPEED", ",f", &[0, 0])),
+            Err("message is too short".to_string())
+        );
+        assert_eq!(
+            parse(&message("/game/SPEED", ",d", &0.5f32.to_be_bytes())),
+            Err("message is too short".to_string())
+        );
+        assert_eq!(
+            parse(&message("/game/DATA", ",b", &[0, 0, 0, 8, 1, 2, 3, 4])),
+            Err("blob is too short".to_string())
+        );
+        assert_eq!(
+            parse(&message("/game/NAME", ",s", b"fast")),
+            Err("string without a terminator".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_messages() {
+        assert_eq!(
+            parse(b"/game"),
+            Err("string without a terminator".to_string())
+        );
+        assert_eq!(
+            parse(&message("game/SPEED", ",f", &0.5f32.to_be_bytes())),
+            Err("invalid address \"game/SPEED\"".to_string())
+        );
+        assert_eq!(
+            parse(&message("/game/SPEED", ",x", &[])),
+            Err("unknown type tag 'x'".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_message_in_bundle() {
+        let packet = bundle(&[
+            message("/game/SPEED", ",f", &0.5f32.to_be_bytes()),
+            message("/game/SPEED", ",x", &[]),
+        ]);
+
+        assert_eq!(parse(&packet), Err("unknown type tag 'x'".to_string()));
+    }
+}
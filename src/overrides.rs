@@ -0,0 +1,348 @@
+use crate::{api, Error, DATA};
+use std::{collections::BTreeMap, env, error, fmt, sync::Mutex};
+
+/// The environment variable with comma separated overrides.
+const ENV_VAR: &str = "CONST_TWEAKER_SET";
+
+/// The command line argument followed by a single override.
+const ARG: &str = "--tweak";
+
+/// A value passed at startup that replaces the value from the source.
+#[derive(Debug)]
+struct Override {
+    /// The value as text, parsed with the type of the constant when it's registered.
+    value: String,
+    /// Whether a constant with the key is registered.
+    used: bool,
+}
+
+lazy_static::lazy_static! {
+    /// The overrides from the environment variable & command line arguments, by key.
+    static ref OVERRIDES: Mutex<BTreeMap<String, Override>> = Mutex::new(collect());
+    /// The overrides that couldn't be parsed or applied.
+    static ref ERRORS: Mutex<Vec<OverrideError>> = Mutex::new(vec![]);
+}
+
+/// Errors with the overrides passed at startup.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverrideError {
+    /// The override isn't written as `key=value`.
+    Syntax(String),
+    /// There's no constant registered with the key.
+    UnknownKey(String),
+    /// The value can't be parsed as the type of the constant, or it's rejected.
+    InvalidValue {
+        key: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for OverrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverrideError::Syntax(text) => {
+                write!(f, "override \"{}\" is not written as key=value", text)
+            }
+            OverrideError::UnknownKey(key) => {
+                write!(f, "override for unknown constant \"{}\"", key)
+            }
+            OverrideError::InvalidValue { key, value, reason } => write!(
+                f,
+                "override \"{}\" for \"{}\" is invalid: {}",
+                value, key, reason
+            ),
+        }
+    }
+}
+
+impl error::Error for OverrideError {}
+
+/// Parse comma separated overrides, as used by the `CONST_TWEAKER_SET` environment variable.
+///
+/// ```rust
+/// let overrides = const_tweaker::parse_overrides("game::GRAVITY=9.8, game::DEBUG=true").unwrap();
+/// assert_eq!(
+///     overrides,
+///     vec![
+///         ("game::GRAVITY".to_string(), "9.8".to_string()),
+///         ("game::DEBUG".to_string(), "true".to_string()),
+///     ]
+/// );
+/// ```
+pub fn parse_overrides(spec: &str) -> Result<Vec<(String, String)>, OverrideError> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_override)
+        .collect()
+}
+
+/// Take the `--tweak key=value` & `--tweak=key=value` overrides out of the command line
+/// arguments, returning them and the arguments that are left.
+///
+/// The overrides are already applied when the constants are registered, this is only needed to
+/// pass the other arguments to an argument parser that rejects unknown arguments:
+///
+/// ```rust
+/// let args = vec!["game", "--tweak", "game::GRAVITY=9.8", "--fullscreen"];
+/// let (overrides, args) = const_tweaker::split_tweak_args(args).unwrap();
+///
+/// assert_eq!(overrides, vec![("game::GRAVITY".to_string(), "9.8".to_string())]);
+/// assert_eq!(args, vec!["game", "--fullscreen"]);
+/// ```
+///
+/// Arguments after `--` are left as they are, so they can't be mistaken for overrides.
+#[allow(clippy::type_complexity)]
+pub fn split_tweak_args<I, S>(
+    args: I,
+) -> Result<(Vec<(String, String)>, Vec<String>), OverrideError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let (overrides, rest) = split_args(args);
+    let overrides = overrides.into_iter().collect::<Result<_, _>>()?;
+
+    Ok((overrides, rest))
+}
+
+/// Check whether all overrides passed at startup are applied.
+///
+/// Overrides are applied while the constants are registered, before `main` is called, so
+/// invalid values are printed to stderr.
+/// Overrides for constants that don't exist can only be detected after all constants are
+/// registered, call this at the start of `main` to fail on any mistake:
+///
+/// ```rust
+/// if let Err(errors) = const_tweaker::check_overrides() {
+///     for error in errors {
+///         eprintln!("{}", error);
+///     }
+///     std::process::exit(1);
+/// }
+/// ```
+pub fn check_overrides() -> Result<(), Vec<OverrideError>> {
+    let mut errors = ERRORS.lock().unwrap().clone();
+    errors.extend(
+        OVERRIDES
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(key, over)| !over.used && !DATA.contains_key(key.as_str()))
+            .map(|(key, _)| OverrideError::UnknownKey(key.clone())),
+    );
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Replace the value of a constant that's just registered when there's an override for it.
+#[doc(hidden)]
+pub fn apply_override(key: &str) {
+    let text = match OVERRIDES.lock().unwrap().get_mut(key) {
+        Some(over) => {
+            over.used = true;
+            over.value.clone()
+        }
+        None => return,
+    };
+
+    let value = DATA
+        .get(key)
+        .expect("Could not get item from map")
+        .value_from_text(&text);
+    let result = value.and_then(|value| {
+        api::set_checked(key, value)
+            .map(|_| ())
+            .map_err(|err| match err {
+                Error::Rejected { reason, .. } => reason,
+                err => err.to_string(),
+            })
+    });
+
+    if let Err(reason) = result {
+        let err = OverrideError::InvalidValue {
+            key: key.to_string(),
+            value: text,
+            reason,
+        };
+        // This is called before main, so there's no other way to report it yet
+        eprintln!("const-tweaker: {}", err);
+        ERRORS.lock().unwrap().push(err);
+    }
+}
+
+/// Split a single `key=value` override.
+fn parse_override(entry: &str) -> Result<(String, String), OverrideError> {
+    entry
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| OverrideError::Syntax(entry.to_string()))
+}
+
+/// Take the overrides out of the command line arguments until `--`, each override can be
+/// invalid on its own.
+#[allow(clippy::type_complexity)]
+fn split_args<I, S>(args: I) -> (Vec<Result<(String, String), OverrideError>>, Vec<String>)
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut overrides = vec![];
+    let mut rest = vec![];
+
+    let mut args = args.into_iter().map(Into::into);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            rest.push(arg);
+            rest.extend(args);
+            break;
+        } else if arg == ARG {
+            overrides.push(match args.next() {
+                Some(entry) => parse_override(&entry),
+                None => Err(OverrideError::Syntax(arg)),
+            });
+        } else if let Some(entry) = arg.strip_prefix(&format!("{}=", ARG)) {
+            overrides.push(parse_override(entry));
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    (overrides, rest)
+}
+
+/// Read the overrides from the environment variable & the command line arguments, the command
+/// line arguments take precedence.
+///
+/// Invalid overrides are reported and skipped, the other overrides are still applied.
+fn collect() -> BTreeMap<String, Override> {
+    let spec = env::var(ENV_VAR).ok();
+    let args = env::args_os()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned());
+    let (overrides, errors) = collect_from(spec.as_deref(), args);
+
+    for err in errors.iter() {
+        eprintln!("const-tweaker: {}", err);
+    }
+    ERRORS.lock().unwrap().extend(errors);
+
+    overrides
+        .into_iter()
+        .map(|(key, value)| (key, Override { value, used: false }))
+        .collect()
+}
+
+/// Parse the overrides from the value of the environment variable & the command line arguments,
+/// returns the valid overrides and the errors.
+#[allow(clippy::type_complexity)]
+fn collect_from<I>(spec: Option<&str>, args: I) -> (Vec<(String, String)>, Vec<OverrideError>)
+where
+    I: IntoIterator<Item = String>,
+{
+    let entries = spec
+        .into_iter()
+        .flat_map(|spec| spec.split(','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_override)
+        .chain(split_args(args).0);
+
+    let mut overrides = vec![];
+    let mut errors = vec![];
+    for entry in entries {
+        match entry {
+            Ok(entry) => overrides.push(entry),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    (overrides, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The arguments as owned strings.
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// The override as owned strings.
+    fn entry(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_override("a::B=1"), Ok(entry("a::B", "1")));
+        // Only the first `=` separates the key, the value is kept as it is
+        assert_eq!(parse_override(" a::B = x=y "), Ok(entry("a::B", " x=y ")));
+        assert_eq!(parse_override("a::B="), Ok(entry("a::B", "")));
+
+        assert!(parse_override("a::B").is_err());
+        assert!(parse_override("=1").is_err());
+        assert_eq!(
+            parse_overrides("a::B=1,,c::D=2, "),
+            Ok(vec![entry("a::B", "1"), entry("c::D", "2")])
+        );
+        assert!(parse_overrides("a::B=1,c::D").is_err());
+    }
+
+    #[test]
+    fn split() {
+        let (overrides, rest) = split_tweak_args(args(&[
+            "game",
+            "--tweak",
+            "a::B=1",
+            "-v",
+            "--tweak=c::D=2",
+            "--",
+            "--tweak",
+            "e::F=3",
+        ]))
+        .unwrap();
+
+        assert_eq!(overrides, vec![entry("a::B", "1"), entry("c::D", "2")]);
+        assert_eq!(rest, args(&["game", "-v", "--", "--tweak", "e::F=3"]));
+
+        assert_eq!(
+            split_tweak_args(args(&["game", "--tweak"])),
+            Err(OverrideError::Syntax("--tweak".to_string()))
+        );
+    }
+
+    #[test]
+    fn skip_invalid() {
+        let (overrides, errors) = collect_from(
+            Some("a::B=1, oops, c::D=2"),
+            args(&[
+                "game",
+                "--tweak",
+                "nope",
+                "--tweak=e::F=3",
+                "--",
+                "--tweak=g::H=4",
+            ]),
+        );
+
+        assert_eq!(
+            overrides,
+            vec![entry("a::B", "1"), entry("c::D", "2"), entry("e::F", "3")]
+        );
+        assert_eq!(
+            errors,
+            vec![
+                OverrideError::Syntax("oops".to_string()),
+                OverrideError::Syntax("nope".to_string())
+            ]
+        );
+    }
+}
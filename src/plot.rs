@@ -0,0 +1,199 @@
+use crate::DATA;
+use dashmap::{DashMap, DashSet};
+use std::{collections::VecDeque, sync::Once, time::SystemTime};
+
+/// How many changes are kept per value for the plots, older changes are dropped.
+const PLOT_SIZE: usize = 100;
+
+lazy_static::lazy_static! {
+    /// The changes of the numeric values by key, oldest first.
+    static ref PLOTS: DashMap<String, VecDeque<Sample>> = DashMap::new();
+    /// The names of the plots that aren't constants.
+    static ref METRICS: DashSet<String> = DashSet::new();
+}
+
+/// A value of a numeric constant at the time it changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    /// When the value changed.
+    pub time: SystemTime,
+    /// The new value, converted to a float.
+    pub value: f64,
+}
+
+/// The last changes of a numeric constant, oldest first, as plotted next to its widget.
+///
+/// The plot starts with the value the constant had when the website was first opened or when it
+/// first changed. Changes made by animations aren't included.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0, max = 10)]
+/// const VALUE: u8 = 0;
+///
+/// # let key = concat!(module_path!(), "::VALUE");
+/// const_tweaker::set(key, 3u8).unwrap();
+/// const_tweaker::set(key, 5u8).unwrap();
+///
+/// let values = const_tweaker::plot(key)
+///     .into_iter()
+///     .map(|sample| sample.value)
+///     .collect::<Vec<_>>();
+/// assert_eq!(values, [3.0, 5.0]);
+/// ```
+pub fn plot(key: &str) -> Vec<Sample> {
+    PLOTS
+        .get(key)
+        .map(|samples| samples.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Add a value of something that isn't a tweakable constant to its plot, e.g. the frame time or
+/// the number of enemies, to see how it reacts to tweaking the constants.
+///
+/// The metrics are plotted on the website under the widgets, with the name as the label.
+/// Like the constants only the last 100 values are kept, so call this at most a few times a
+/// second, e.g. once every 30 frames. Values that aren't finite are ignored.
+///
+/// ```rust
+/// // In the game loop
+/// # let frame_time = 0.016;
+/// const_tweaker::plot_metric("frame time", frame_time);
+///
+/// assert_eq!(const_tweaker::plot("frame time")[0].value, 0.016);
+/// ```
+pub fn plot_metric(name: &str, value: f64) {
+    if !value.is_finite() {
+        return;
+    }
+
+    METRICS.insert(name.to_string());
+    push(name, value);
+}
+
+/// The names of all metrics, sorted.
+pub(crate) fn metrics() -> Vec<String> {
+    let mut metrics = METRICS
+        .iter()
+        .map(|name| name.key().clone())
+        .collect::<Vec<_>>();
+    metrics.sort();
+
+    metrics
+}
+
+/// The plots of all numeric constants & metrics that have one, sorted by key.
+pub(crate) fn plots() -> Vec<(String, Vec<Sample>)> {
+    let mut plots = PLOTS
+        .iter()
+        .map(|kv| (kv.key().clone(), kv.value().iter().copied().collect()))
+        .collect::<Vec<_>>();
+    plots.sort_by(|a, b| a.0.cmp(&b.0));
+
+    plots
+}
+
+/// Add the current value of the constant to its plot when it's numeric.
+pub(crate) fn record(key: &str) {
+    if let Some(value) = numeric_value(key) {
+        push(key, value);
+    }
+}
+
+/// Start the plots of the numeric constants that didn't change yet with their current value, only
+/// the first time it's called.
+pub(crate) fn start() {
+    static START: Once = Once::new();
+    START.call_once(start_all);
+}
+
+/// Start the plots of all numeric constants that didn't change yet.
+fn start_all() {
+    // Collect the keys first so the values aren't locked while the plots are
+    let keys = DATA
+        .iter()
+        .filter(|kv| !PLOTS.contains_key(*kv.key()))
+        .map(|kv| *kv.key())
+        .collect::<Vec<_>>();
+
+    for key in keys {
+        if let Some(value) = numeric_value(key) {
+            // Another thread might have recorded a change in the meantime
+            if !PLOTS.contains_key(key) {
+                push(key, value);
+            }
+        }
+    }
+}
+
+/// The current value as a float, `None` for booleans & strings.
+fn numeric_value(key: &str) -> Option<f64> {
+    DATA.get(key)?.current_value().to_json().as_f64()
+}
+
+/// Add a value to the plot, dropping the oldest one when it's full.
+fn push(key: &str, value: f64) {
+    let mut samples = PLOTS.entry(key.to_string()).or_default();
+    if samples.len() >= PLOT_SIZE {
+        samples.pop_front();
+    }
+    samples.push_back(Sample {
+        time: SystemTime::now(),
+        value,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_history, test_field};
+
+    fn values(key: &str) -> Vec<f64> {
+        plot(key).into_iter().map(|sample| sample.value).collect()
+    }
+
+    #[test]
+    fn capacity() {
+        for value in 0..PLOT_SIZE + 50 {
+            push("plot::tests::CAPACITY", value as f64);
+        }
+
+        // The oldest values are dropped
+        let expected = (50..PLOT_SIZE + 50)
+            .map(|value| value as f64)
+            .collect::<Vec<_>>();
+        assert_eq!(values("plot::tests::CAPACITY"), expected);
+    }
+
+    #[test]
+    fn oldest_first() {
+        for value in [3.0, 1.0, 2.0] {
+            push("plot::tests::ORDER", value);
+        }
+
+        let samples = plot("plot::tests::ORDER");
+        assert_eq!(values("plot::tests::ORDER"), [3.0, 1.0, 2.0]);
+        assert!(samples.windows(2).all(|pair| pair[0].time <= pair[1].time));
+    }
+
+    #[test]
+    fn constants() {
+        let _history = lock_history();
+        test_field("plot::tests::CONSTANT", None);
+        crate::set("plot::tests::CONSTANT", 2.0).unwrap();
+
+        assert_eq!(values("plot::tests::CONSTANT"), [2.0]);
+        assert!(!metrics().contains(&"plot::tests::CONSTANT".to_string()));
+        assert!(plot("plot::tests::MISSING").is_empty());
+    }
+
+    #[test]
+    fn metric() {
+        plot_metric("plot tests metric", 1.5);
+        plot_metric("plot tests metric", f64::NAN);
+        plot_metric("plot tests metric", 2.5);
+
+        assert_eq!(values("plot tests metric"), [1.5, 2.5]);
+        assert!(metrics().contains(&"plot tests metric".to_string()));
+        assert!(plots().iter().any(|(key, _)| key == "plot tests metric"));
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{animation, api, history, notify, Error, Origin, Value, DATA};
+use std::{
+    collections::BTreeMap,
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The values of a preset by key, as stored in the file.
+type Preset = BTreeMap<String, serde_json::Value>;
+
+lazy_static::lazy_static! {
+    /// The file where the presets are stored.
+    static ref PRESETS_PATH: Mutex<PathBuf> = Mutex::new(PathBuf::from("const-tweaker-presets.json"));
+}
+
+/// Errors returned when saving or applying presets.
+#[derive(Debug)]
+pub enum PresetError {
+    /// Reading or writing the presets file failed.
+    Io(io::Error),
+    /// The presets file isn't valid JSON.
+    Parse(serde_json::Error),
+    /// There's no preset with the name.
+    UnknownPreset(String),
+    /// A value in the preset can't be applied, none of the values are applied.
+    Value(Error),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Io(err) => write!(f, "could not access the presets file: {}", err),
+            PresetError::Parse(err) => write!(f, "could not parse the presets file: {}", err),
+            PresetError::UnknownPreset(name) => write!(f, "no preset named \"{}\"", name),
+            PresetError::Value(err) => write!(f, "could not apply the preset: {}", err),
+        }
+    }
+}
+
+impl error::Error for PresetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PresetError::Io(err) => Some(err),
+            PresetError::Parse(err) => Some(err),
+            PresetError::UnknownPreset(_) => None,
+            PresetError::Value(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for PresetError {
+    fn from(err: io::Error) -> Self {
+        PresetError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(err: serde_json::Error) -> Self {
+        PresetError::Parse(err)
+    }
+}
+
+/// Change the file where presets are stored.
+///
+/// By default this is `const-tweaker-presets.json` in the current working directory, which is the
+/// root of the project when running with `cargo run`.
+pub fn set_presets_path<P>(path: P)
+where
+    P: AsRef<Path>,
+{
+    *PRESETS_PATH.lock().unwrap() = path.as_ref().to_path_buf();
+}
+
+/// Store all current values as a preset, overwriting the preset if it already exists.
+///
+/// ```rust,no_run
+/// // Tune the values for the easy difficulty in the web interface, then
+/// const_tweaker::save_preset("easy").unwrap();
+///
+/// // Later switch back to it
+/// const_tweaker::apply_preset("easy").unwrap();
+/// ```
+pub fn save_preset(name: &str) -> Result<(), PresetError> {
+    let preset = DATA
+        .iter()
+        .map(|kv| {
+            // Animated values are stored as the value they return to
+            let value = animation::static_value(kv.key())
+                .map(|value| value.to_json())
+                .unwrap_or_else(|| kv.value().value_json());
+
+            (kv.key().to_string(), value)
+        })
+        .collect::<Preset>();
+
+    let mut presets = load()?;
+    presets.insert(name.to_string(), preset);
+
+    store(&presets)
+}
+
+/// The names of all stored presets, sorted alphabetically.
+pub fn presets() -> Result<Vec<String>, PresetError> {
+    Ok(load()?.into_keys().collect())
+}
+
+/// Remove a stored preset.
+pub fn delete_preset(name: &str) -> Result<(), PresetError> {
+    let mut presets = load()?;
+    presets
+        .remove(name)
+        .ok_or_else(|| PresetError::UnknownPreset(name.to_string()))?;
+
+    store(&presets)
+}
+
+/// Set all values from a preset.
+///
+/// This is atomic: when any of the values can't be applied none of them are.
+/// Keys in the preset of constants that don't exist anymore are ignored, and constants that
+/// didn't exist yet when the preset was saved keep their current value.
+///
+/// Returns the keys of the values that changed.
+pub fn apply_preset(name: &str) -> Result<Vec<&'static str>, PresetError> {
+    let preset = load()?
+        .remove(name)
+        .ok_or_else(|| PresetError::UnknownPreset(name.to_string()))?;
+
+    // Convert all values first so type mismatches don't leave a partially applied preset
+    let values = preset
+        .into_iter()
+        .filter_map(|(key, json)| {
+            let field = DATA.get(&*key)?;
+            let key = *field.key();
+
+            Some(
+                field
+                    .value_from_json(json)
+                    .map(|value| (key, value))
+                    .map_err(|reason| Error::Rejected {
+                        key: key.to_string(),
+                        reason,
+                    }),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PresetError::Value)?;
+
+    let old_values = apply_all(values).map_err(PresetError::Value)?;
+
+    let changed = old_values
+        .into_iter()
+        .filter_map(|(key, old_value)| {
+            let new_value = DATA.get(key)?.current_value();
+
+            (new_value != old_value).then_some((key, old_value, new_value))
+        })
+        .collect::<Vec<_>>();
+
+    // The fields must be unlocked before notifying, so the callbacks can read them
+    for (key, old_value, new_value) in changed.iter() {
+        history::record(key, old_value.clone(), new_value.clone(), Origin::Preset);
+        notify(key);
+    }
+    let changed = changed.into_iter().map(|(key, _, _)| key).collect();
+
+    Ok(changed)
+}
+
+/// Set all values, restoring the values already set when one of them is rejected.
+///
+/// Returns the old values.
+pub(crate) fn apply_all(
+    values: Vec<(&'static str, Value)>,
+) -> Result<Vec<(&'static str, Value)>, Error> {
+    let mut old_values = Vec::with_capacity(values.len());
+
+    for (key, value) in values {
+        let old_value = match api::set_checked(key, value) {
+            Ok(old_value) => old_value,
+            Err(err) => {
+                // Put back the values that are already applied without validating them, the range
+                // or the validation function might reject them by now
+                for (key, old_value) in old_values {
+                    DATA.get_mut(key)
+                        .expect("Could not get item from map")
+                        .restore_value(old_value);
+                }
+
+                return Err(err);
+            }
+        };
+
+        old_values.push((key, old_value));
+    }
+
+    Ok(old_values)
+}
+
+/// Read all presets from the file, an empty list when the file doesn't exist yet.
+fn load() -> Result<BTreeMap<String, Preset>, PresetError> {
+    let path = PRESETS_PATH.lock().unwrap().clone();
+
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Write all presets to the file.
+fn store(presets: &BTreeMap<String, Preset>) -> Result<(), PresetError> {
+    let path = PRESETS_PATH.lock().unwrap().clone();
+
+    fs::write(path, serde_json::to_string_pretty(presets)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_history, test_field};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    lazy_static::lazy_static! {
+        /// The path of the presets is global, so the tests using it can't run in parallel.
+        static ref PATH_TEST: Mutex<()> = Mutex::new(());
+    }
+
+    /// Use a presets file in the temporary directory, unique for the test.
+    fn use_presets(name: &str, contents: Option<&str>) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "const-tweaker-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        match contents {
+            Some(contents) => fs::write(&path, contents).unwrap(),
+            None => {
+                let _ = fs::remove_file(&path);
+            }
+        }
+        set_presets_path(&path);
+
+        path
+    }
+
+    fn value(key: &str) -> Value {
+        DATA.get(key).unwrap().current_value()
+    }
+
+    #[test]
+    fn save() {
+        let _history = lock_history();
+        let _path = PATH_TEST.lock().unwrap();
+        test_field("preset::tests::SAVED", None);
+        crate::set("preset::tests::SAVED", 2.0).unwrap();
+        let path = use_presets("save", None);
+
+        save_preset("fast").unwrap();
+        save_preset("slow").unwrap();
+        delete_preset("slow").unwrap();
+
+        assert_eq!(presets().unwrap(), ["fast"]);
+        let presets: BTreeMap<String, Preset> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(presets["fast"]["preset::tests::SAVED"], 2.0);
+        assert!(matches!(
+            delete_preset("slow"),
+            Err(PresetError::UnknownPreset(_))
+        ));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn apply() {
+        let _history = lock_history();
+        let _path = PATH_TEST.lock().unwrap();
+        test_field("preset::tests::APPLIED", None);
+        test_field("preset::tests::UNCHANGED", None);
+        // Keys of constants that don't exist anymore are ignored
+        let path = use_presets(
+            "apply",
+            Some(
+                r#"{"fast": {
+                    "preset::tests::APPLIED": 3.0,
+                    "preset::tests::UNCHANGED": 1.0,
+                    "preset::tests::REMOVED": 4.0
+                }}"#,
+            ),
+        );
+
+        assert_eq!(apply_preset("fast").unwrap(), ["preset::tests::APPLIED"]);
+        assert_eq!(value("preset::tests::APPLIED"), Value::F64(3.0));
+        assert!(matches!(
+            apply_preset("slow"),
+            Err(PresetError::UnknownPreset(_))
+        ));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_file() {
+        let _path = PATH_TEST.lock().unwrap();
+        let path = use_presets("invalid", Some("{"));
+
+        assert!(matches!(presets(), Err(PresetError::Parse(_))));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wrong_type() {
+        let _path = PATH_TEST.lock().unwrap();
+        test_field("preset::tests::WRONG_TYPE", None);
+        let path = use_presets(
+            "wrong_type",
+            Some(r#"{"fast": {"preset::tests::WRONG_TYPE": "fast"}}"#),
+        );
+
+        assert!(matches!(apply_preset("fast"), Err(PresetError::Value(_))));
+        assert_eq!(value("preset::tests::WRONG_TYPE"), Value::F64(1.0));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rollback_on_rejection() {
+        // Accepts the current value at first, rejects it once the preset is being applied
+        static STRICT: AtomicBool = AtomicBool::new(false);
+        test_field(
+            "preset::tests::ROLLED_BACK",
+            Some(|value| {
+                if STRICT.load(Ordering::SeqCst) && *value < 2.0 {
+                    Err("too slow".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        );
+        test_field(
+            "preset::tests::REJECTED",
+            Some(|value| {
+                STRICT.store(true, Ordering::SeqCst);
+                if *value > 5.0 {
+                    Err("too fast".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        );
+
+        let err = apply_all(vec![
+            ("preset::tests::ROLLED_BACK", Value::F64(3.0)),
+            ("preset::tests::REJECTED", Value::F64(6.0)),
+        ])
+        .unwrap_err();
+
+        assert!(err.to_string().contains("too fast"), "{}", err);
+        assert_eq!(value("preset::tests::ROLLED_BACK"), Value::F64(1.0));
+        assert_eq!(value("preset::tests::REJECTED"), Value::F64(1.0));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{api, Error, Origin, DATA};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, error, fmt,
+    fs::{self, File},
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::{
+        mpsc::{self, Sender},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+lazy_static::lazy_static! {
+    /// The file the changes are written to, `None` when not recording.
+    static ref RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+}
+
+/// An active recording.
+struct Recording {
+    /// The changes are written from another thread, so changing a value doesn't wait for the file.
+    sender: Sender<Entry>,
+    /// The thread writing to the file, it stops when the sender is dropped.
+    writer: JoinHandle<()>,
+    /// When the recording started, the times are relative to it.
+    start: Instant,
+}
+
+impl Recording {
+    /// Wait until all changes are written to the file.
+    fn finish(self) {
+        drop(self.sender);
+        let _ = self.writer.join();
+    }
+}
+
+/// A single change as stored on a line of the file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Seconds since the recording started.
+    time: f64,
+    /// The full path of the constant.
+    key: String,
+    /// The new value.
+    value: serde_json::Value,
+}
+
+/// Errors returned when recording or replaying changes.
+#[derive(Debug)]
+pub enum RecordingError {
+    /// Reading or writing the recording failed.
+    Io(io::Error),
+    /// A line of the recording isn't a valid change.
+    Parse {
+        /// The line number, starting at `1`.
+        line: usize,
+        err: serde_json::Error,
+    },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(err) => write!(f, "could not access the recording: {}", err),
+            RecordingError::Parse { line, err } => {
+                write!(f, "could not parse line {} of the recording: {}", line, err)
+            }
+        }
+    }
+}
+
+impl error::Error for RecordingError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RecordingError::Io(err) => Some(err),
+            RecordingError::Parse { err, .. } => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(err: io::Error) -> Self {
+        RecordingError::Io(err)
+    }
+}
+
+/// Write every change of a value to a file with the time since the recording started, so it can
+/// be replayed with [`replay`].
+///
+/// Changes from everywhere are recorded: the web interface, the API, presets, undoing etc.
+/// The file is overwritten, and a recording that's already running is stopped.
+/// Recording can also be started before `main` by setting the `CONST_TWEAKER_RECORD` environment
+/// variable to the path of the file.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0, max = 100)]
+/// const VALUE: u32 = 0;
+///
+/// # let key = concat!(module_path!(), "::VALUE");
+/// let path = std::env::temp_dir().join(format!(
+///     "const-tweaker-recording-{}.jsonl",
+///     std::process::id()
+/// ));
+///
+/// const_tweaker::start_recording(&path).unwrap();
+/// const_tweaker::set(key, 10u32).unwrap();
+/// const_tweaker::set(key, 20u32).unwrap();
+/// const_tweaker::stop_recording();
+///
+/// VALUE.reset();
+/// // Apply the same changes again at the same times, and wait for it to finish
+/// const_tweaker::replay(&path).unwrap().join().unwrap();
+/// assert_eq!(*VALUE, 20);
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+pub fn start_recording<P>(path: P) -> Result<(), RecordingError>
+where
+    P: AsRef<Path>,
+{
+    let file = File::create(path)?;
+
+    let (sender, receiver) = mpsc::channel::<Entry>();
+    let writer = thread::spawn(move || {
+        // Every line is written immediately, so the recording is complete when the application
+        // crashes
+        let mut file = LineWriter::new(file);
+        for entry in receiver {
+            let line = serde_json::to_string(&entry).expect("Could not encode JSON");
+            if let Err(err) = writeln!(file, "{}", line) {
+                eprintln!(
+                    "const-tweaker: could not write to the recording, stopping: {}",
+                    err
+                );
+                return;
+            }
+        }
+    });
+
+    let previous = RECORDING.lock().unwrap().replace(Recording {
+        sender,
+        writer,
+        start: Instant::now(),
+    });
+    if let Some(previous) = previous {
+        previous.finish();
+    }
+
+    Ok(())
+}
+
+/// Stop writing changes to the file started with [`start_recording`].
+///
+/// Returns after all changes are written to the file.
+pub fn stop_recording() {
+    let recording = RECORDING.lock().unwrap().take();
+    if let Some(recording) = recording {
+        recording.finish();
+    }
+}
+
+/// Apply the changes from a file written by [`start_recording`] at the same times relative to
+/// now as they were recorded.
+///
+/// The changes are applied from a new thread, join the returned handle to wait for the last one.
+/// Changes that can't be applied are printed to stderr and skipped.
+/// A replay can also be started before `main` by setting the `CONST_TWEAKER_REPLAY` environment
+/// variable to the path of the file.
+pub fn replay<P>(path: P) -> Result<JoinHandle<()>, RecordingError>
+where
+    P: AsRef<Path>,
+{
+    let contents = fs::read_to_string(path)?;
+    let entries = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str::<Entry>(line).map_err(|err| RecordingError::Parse {
+                line: index + 1,
+                err,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let start = Instant::now();
+    Ok(thread::spawn(move || {
+        for entry in entries {
+            let time = start + Duration::from_secs_f64(entry.time.max(0.0));
+            if let Some(wait) = time.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+
+            if let Err(err) = apply(entry) {
+                eprintln!("const-tweaker: could not replay change: {}", err);
+            }
+        }
+    }))
+}
+
+/// Send the new value to the thread writing the recording when recording.
+pub(crate) fn record(key: &str) {
+    let mut recording = RECORDING.lock().unwrap();
+    let active = match recording.as_mut() {
+        Some(active) => active,
+        None => return,
+    };
+
+    let value = match DATA.get(key) {
+        Some(field) => field.value_json(),
+        None => return,
+    };
+    let entry = Entry {
+        time: active.start.elapsed().as_secs_f64(),
+        key: key.to_string(),
+        value,
+    };
+
+    // The thread stops when writing fails, the error is already printed
+    if active.sender.send(entry).is_err() {
+        recording.take();
+    }
+}
+
+/// Start recording or replaying from the `CONST_TWEAKER_RECORD` & `CONST_TWEAKER_REPLAY`
+/// environment variables.
+pub(crate) fn start_from_env() {
+    if let Some(path) = env::var_os("CONST_TWEAKER_RECORD").filter(|path| !path.is_empty()) {
+        if let Err(err) = start_recording(&path) {
+            eprintln!("const-tweaker: CONST_TWEAKER_RECORD: {}", err);
+        }
+    }
+
+    if let Some(path) = env::var_os("CONST_TWEAKER_REPLAY").filter(|path| !path.is_empty()) {
+        if let Err(err) = replay(&path) {
+            eprintln!("const-tweaker: CONST_TWEAKER_REPLAY: {}", err);
+        }
+    }
+}
+
+/// Set the value from a recorded change.
+fn apply(entry: Entry) -> Result<(), Error> {
+    let Entry { key, value, .. } = entry;
+    let value = DATA
+        .get(&*key)
+        .ok_or_else(|| Error::UnknownKey(key.clone()))?
+        .value_from_json(value)
+        .map_err(|reason| Error::Rejected {
+            key: key.clone(),
+            reason,
+        })?;
+
+    api::set_from(&key, value, Origin::Replay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_history, test_field};
+
+    #[test]
+    fn record_changes() {
+        let _history = lock_history();
+        test_field("recording::tests::RECORDED", None);
+        let path = env::temp_dir().join(format!(
+            "const-tweaker-{}-recording.jsonl",
+            std::process::id()
+        ));
+
+        start_recording(&path).unwrap();
+        api::set("recording::tests::RECORDED", 2.0).unwrap();
+        api::set("recording::tests::RECORDED", 3.0).unwrap();
+        stop_recording();
+        api::set("recording::tests::RECORDED", 4.0).unwrap();
+
+        // Other tests change values at the same time
+        let values = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Entry>(line).unwrap())
+            .filter(|entry| entry.key == "recording::tests::RECORDED")
+            .map(|entry| entry.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![serde_json::json!(2.0), serde_json::json!(3.0)]);
+
+        fs::remove_file(path).unwrap();
+    }
+}
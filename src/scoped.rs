@@ -0,0 +1,250 @@
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// A value stored for the rest of the program, shared by all overrides with the same value.
+type Interned = &'static (dyn Any + Send + Sync);
+
+thread_local! {
+    /// The values overriding the constants on this thread by key, innermost last.
+    static OVERRIDES: RefCell<HashMap<&'static str, Vec<Interned>>> = RefCell::new(HashMap::new());
+}
+
+lazy_static::lazy_static! {
+    /// Every distinct value a constant was overridden with, by type & bytes.
+    static ref INTERNED: Mutex<HashMap<(TypeId, Vec<u8>), Interned>> = Mutex::new(HashMap::new());
+}
+
+/// How many overrides are active on all threads, so reading a constant doesn't have to look at
+/// the thread local map when there are none.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// A tweakable constant, implemented by `#[const_tweaker::tweak]`.
+pub trait Tweakable {
+    /// The type that can be assigned to the constant, `String` for `&str` constants.
+    type Type: OverrideValue;
+
+    /// The full path of the constant, e.g. `my_game::physics::GRAVITY`.
+    fn key(&self) -> &'static str;
+}
+
+/// A value a constant can be overridden with, implemented for the types of tweakable constants.
+pub trait OverrideValue: Any + Send + Sync {
+    /// The bytes telling the value apart from other values of the type.
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+macro_rules! impl_override_value {
+    ($($ty:ty),+) => {
+        $(impl OverrideValue for $ty {
+            fn to_bytes(&self) -> Vec<u8> {
+                self.to_ne_bytes().to_vec()
+            }
+        })+
+    };
+}
+
+impl_override_value!(i8, u8, i16, u16, i32, u32, i64, u64, usize);
+
+impl OverrideValue for f32 {
+    fn to_bytes(&self) -> Vec<u8> {
+        // Compare the bits, so every `NaN` & `-0.0` are kept apart
+        self.to_bits().to_ne_bytes().to_vec()
+    }
+}
+
+impl OverrideValue for f64 {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bits().to_ne_bytes().to_vec()
+    }
+}
+
+impl OverrideValue for bool {
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+}
+
+impl OverrideValue for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+/// Removes the override when it goes out of scope, also when the closure panics.
+struct Guard {
+    key: &'static str,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        OVERRIDES.with(|overrides| {
+            if let Some(values) = overrides.borrow_mut().get_mut(self.key) {
+                values.pop();
+            }
+        });
+        ACTIVE.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Store the value for the rest of the program, unless the same value is already stored.
+fn intern<T>(value: T) -> Interned
+where
+    T: OverrideValue,
+{
+    *INTERNED
+        .lock()
+        .unwrap()
+        .entry((TypeId::of::<T>(), value.to_bytes()))
+        .or_insert_with(|| Box::leak(Box::new(value)))
+}
+
+/// Run a closure with a constant set to a different value, only on the current thread.
+///
+/// This is meant for tests: other threads, including other tests running in parallel, keep
+/// seeing the normal value, and the value is restored when the closure returns or panics.
+/// Overrides can be nested, the innermost one is used.
+///
+/// The value isn't checked against the range or the validation function, and it's not shown
+/// in the web interface. Reading the constant returns a `'static` reference, so every distinct
+/// value is kept until the program exits: overriding with the same values again doesn't use more
+/// memory.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0.0, max = 100.0)]
+/// const GRAVITY: f64 = 9.8;
+///
+/// const_tweaker::with_override(&GRAVITY, 20.0, || {
+///     assert_eq!(*GRAVITY, 20.0);
+/// });
+/// assert_eq!(*GRAVITY, 9.8);
+///
+/// #[const_tweaker::tweak]
+/// const NAME: &str = "Player";
+///
+/// // Strings don't have to be static
+/// let name = format!("Player {}", 2);
+/// const_tweaker::with_override(&NAME, name, || {
+///     assert_eq!(&*NAME, "Player 2");
+/// });
+/// ```
+pub fn with_override<C, V, F, R>(constant: &C, value: V, f: F) -> R
+where
+    C: Tweakable,
+    V: Into<C::Type>,
+    F: FnOnce() -> R,
+{
+    let key = constant.key();
+
+    OVERRIDES.with(|overrides| {
+        overrides
+            .borrow_mut()
+            .entry(key)
+            .or_default()
+            .push(intern(value.into()))
+    });
+    ACTIVE.fetch_add(1, Ordering::SeqCst);
+    let _guard = Guard { key };
+
+    f()
+}
+
+/// The innermost override of the constant on this thread.
+#[doc(hidden)]
+pub fn scoped_override<T>(key: &str) -> Option<&'static T>
+where
+    T: Any,
+{
+    if ACTIVE.load(Ordering::SeqCst) == 0 {
+        return None;
+    }
+
+    OVERRIDES
+        .with(|overrides| overrides.borrow().get(key)?.last().copied())?
+        .downcast_ref::<T>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{panic, thread};
+
+    /// A constant only used by the tests of this module, so they don't need the macro.
+    struct Speed;
+
+    impl Tweakable for Speed {
+        type Type = f64;
+
+        fn key(&self) -> &'static str {
+            "scoped::tests::SPEED"
+        }
+    }
+
+    /// The overridden value on this thread.
+    fn speed() -> Option<f64> {
+        scoped_override::<f64>(Speed.key()).copied()
+    }
+
+    #[test]
+    fn nested() {
+        with_override(&Speed, 2.0, || {
+            assert_eq!(speed(), Some(2.0));
+
+            with_override(&Speed, 3.0, || assert_eq!(speed(), Some(3.0)));
+
+            assert_eq!(speed(), Some(2.0));
+        });
+
+        assert_eq!(speed(), None);
+    }
+
+    #[test]
+    fn restored_after_panic() {
+        let result = panic::catch_unwind(|| with_override(&Speed, 4.0, || panic!("test")));
+
+        assert!(result.is_err());
+        assert_eq!(speed(), None);
+    }
+
+    #[test]
+    fn other_threads() {
+        with_override(&Speed, 5.0, || {
+            assert_eq!(thread::spawn(speed).join().unwrap(), None);
+            assert_eq!(speed(), Some(5.0));
+        });
+    }
+
+    #[test]
+    fn references_outlive_the_override() {
+        let value = with_override(&Speed, 6.0, || scoped_override::<f64>(Speed.key()).unwrap());
+
+        assert_eq!(*value, 6.0);
+    }
+
+    #[test]
+    fn values_are_stored_once() {
+        let first = with_override(&Speed, 7.0, || scoped_override::<f64>(Speed.key()).unwrap());
+        let second = with_override(&Speed, 7.0, || scoped_override::<f64>(Speed.key()).unwrap());
+        let negative = with_override(&Speed, -0.0, || {
+            scoped_override::<f64>(Speed.key()).unwrap()
+        });
+        let positive = with_override(&Speed, 0.0, || scoped_override::<f64>(Speed.key()).unwrap());
+
+        assert!(std::ptr::eq(first, second));
+        assert!(negative.is_sign_negative() && positive.is_sign_positive());
+    }
+
+    #[test]
+    fn other_types() {
+        assert_eq!(scoped_override::<String>(Speed.key()), None);
+        with_override(&Speed, 8.0, || {
+            assert_eq!(scoped_override::<f32>(Speed.key()), None);
+        });
+    }
+}
@@ -1,4 +1,10 @@
 var changed_values = new Map();
+var last_generation = null;
+
+// The token is stored in a cookie by the server, remove it from the address bar
+if (new URLSearchParams(location.search).has('token')) {
+	history.replaceState(null, '', location.pathname);
+}
 
 function send(source, value, data_type) {
 	// Change the label
@@ -6,20 +12,98 @@ function send(source, value, data_type) {
 	if (label_element) {
 		label_element.innerHTML = value;
 	}
+	// Keep the number box in sync with the slider
+	var number_element = document.getElementById(source + '_number');
+	if (number_element) {
+		number_element.value = value;
+	}
 
-	// Make the request
-	fetch('/set/' + data_type, {
+	// Make the request, and append it to the output text when it's accepted
+	post_value('/set/' + data_type, source, value).then(value => {
+		if (value !== undefined) {
+			changed_value(source, value, data_type);
+			update_history();
+		}
+	});
+}
+
+function send_text(source, text, data_type) {
+	// The server parses the text, only update the widget after it's accepted
+	post_value('/set/text', source, text).then(value => {
+		if (value !== undefined) {
+			set_widget_value(source, value);
+			changed_value(source, value, data_type);
+			update_history();
+		}
+	});
+}
+
+function post_value(url, source, value) {
+	return fetch(url, {
 		method: 'POST',
 		headers: {
 			'Content-Type': 'application/json'
 		},
 		body: JSON.stringify({key: source, value: value})
-	}).catch(err => {
-		document.getElementById('status').textContent = 'HTTP Error: ' + err;
-	});
+	})
+		.then(response => {
+			if (response.ok) {
+				show_error(source, '');
+				return response.json();
+			} else {
+				// The value is rejected, show why next to the widget and restore the old value
+				return response.json().then(({error, value}) => {
+					show_error(source, error);
+					set_widget_value(source, value);
+				});
+			}
+		})
+		.catch(err => {
+			document.getElementById('status').textContent = 'HTTP Error: ' + err;
+		});
+}
+
+function show_error(source, err) {
+	var error_element = document.getElementById(source + '_error');
+	if (error_element) {
+		error_element.textContent = err;
+	}
+}
+
+function scaled_value(slider) {
+	let min = Number(slider.dataset.min);
+	let max = Number(slider.dataset.max);
+	let step = Number(slider.dataset.step);
+	let position = Number(slider.value);
+
+	// Convert the position of the slider to the value using the curve
+	let value;
+	if (slider.dataset.scale == 'log') {
+		value = Math.exp(Math.log(min) + position * (Math.log(max) - Math.log(min)));
+	} else {
+		value = min + (max - min) * Math.pow(position, Number(slider.dataset.exponent));
+	}
 
-	// Append it to the output text
-	changed_value(source, value, data_type)
+	// Snap to the step, and get rid of floating point errors introduced by that
+	value = Number((Math.round(value / step) * step).toPrecision(12));
+
+	return Math.min(Math.max(value, min), max);
+}
+
+function scaled_position(slider, value) {
+	let min = Number(slider.dataset.min);
+	let max = Number(slider.dataset.max);
+
+	// Convert the value to the position of the slider using the inverse of the curve
+	let position;
+	if (slider.dataset.scale == 'log') {
+		position = (Math.log(value) - Math.log(min)) / (Math.log(max) - Math.log(min));
+	} else {
+		position = Math.pow((value - min) / (max - min), 1 / Number(slider.dataset.exponent));
+	}
+
+	// Values outside of the range or invalid for the curve are put at the edges
+	return isNaN(position) ? 0 : Math.min(Math.max(position, 0), 1);
 }
 
 function split_name(source) {
@@ -36,6 +120,15 @@ function split_name(source) {
 function changed_value(source, value, data_type) {
 	let names = split_name(source);
 
+	// Setting the value as declared in the source again isn't a change
+	let widget = document.querySelector('.widget[data-key="' + CSS.escape(source) + '"]');
+	if (widget && JSON.parse(widget.dataset.default) === value) {
+		changed_values.delete(source);
+		set_modified(source, false);
+		update_output(names.module);
+		return;
+	}
+
 	// Create a line and add it to the map
 	var line;
 	if (data_type == "string") {
@@ -45,20 +138,218 @@ function changed_value(source, value, data_type) {
 	}
 	changed_values.set(source, line);
 
-	// Print the whole map for this source
+	set_modified(source, true);
+	update_output(names.module);
+}
+
+function update_output(module) {
+	// Print the whole map for this module
 	let output = "";
 	for (let [map_source, line] of changed_values) {
-		if (names.module == split_name(map_source).module) {
+		if (module == split_name(map_source).module) {
 			output += line + "\n";
 		}
 	}
-	var output_text = document.getElementById(names.module.replace("::", "_") + "_output");
+	var output_text = document.getElementById(module.split("::").join("_") + "_output");
 	output_text.value = output;
 }
 
+function set_modified(source, modified) {
+	let widget = document.querySelector('.widget[data-key="' + CSS.escape(source) + '"]');
+	if (widget) {
+		widget.dataset.modified = modified;
+	}
+}
+
+function reset(source) {
+	post_json('/reset', {key: source}).then(update_widgets);
+}
+
+function reset_module(module) {
+	post_json('/reset/module', {module: module}).then(update_widgets);
+}
+
+function reset_all() {
+	post_json('/reset/all', {}).then(update_widgets);
+}
+
+function apply_preset() {
+	let name = document.getElementById('preset_select').value;
+	if (name) {
+		post_json('/preset/apply', {name: name}).then(update_widgets);
+	}
+}
+
+function save_preset() {
+	let name = document.getElementById('preset_name').value.trim();
+	if (name) {
+		post_json('/preset/save', {name: name}).then(presets => {
+			update_presets(presets);
+			document.getElementById('preset_select').value = name;
+		});
+	}
+}
+
+function delete_preset() {
+	let name = document.getElementById('preset_select').value;
+	if (name) {
+		post_json('/preset/delete', {name: name}).then(update_presets);
+	}
+}
+
+function update_presets(presets) {
+	if (presets === undefined) {
+		return;
+	}
+
+	let select = document.getElementById('preset_select');
+	select.innerHTML = '';
+	for (let name of presets) {
+		let option = document.createElement('option');
+		option.value = name;
+		option.textContent = name;
+		select.appendChild(option);
+	}
+}
+
+function undo() {
+	post_json('/history/undo', {}).then(update_widgets);
+}
+
+function redo() {
+	post_json('/history/redo', {}).then(update_widgets);
+}
+
+function revert(id) {
+	post_json('/history/revert', {id: id}).then(update_widgets);
+}
+
+function update_history() {
+	fetch('/history')
+		.then(response => response.json())
+		.then(entries => {
+			let tbody = document.getElementById('history_entries');
+			tbody.innerHTML = '';
+			for (let {id, key, old, new: value, timestamp, origin} of entries) {
+				let row = tbody.insertRow();
+				row.insertCell().textContent = new Date(timestamp).toLocaleTimeString();
+				row.insertCell().textContent = key;
+				row.insertCell().textContent = old + ' → ' + value;
+				row.insertCell().innerHTML = '<span class="tag">' + origin + '</span>';
+
+				let button = document.createElement('button');
+				button.className = 'button is-small';
+				button.title = 'Set the value back to ' + old;
+				button.textContent = 'Revert';
+				button.onclick = () => revert(id);
+				row.insertCell().appendChild(button);
+			}
+		})
+		.catch(err => {
+			document.getElementById('status').textContent = 'HTTP Error: ' + err;
+		});
+}
+
+document.addEventListener('keydown', event => {
+	// Keep the undo of the browser when typing text
+	if (event.target.type == 'text' || event.target.type == 'search' || event.target.type == 'number') {
+		return;
+	}
+
+	if ((event.ctrlKey || event.metaKey) && event.key.toLowerCase() == 'z') {
+		event.preventDefault();
+		if (event.shiftKey) {
+			redo();
+		} else {
+			undo();
+		}
+	} else if ((event.ctrlKey || event.metaKey) && event.key.toLowerCase() == 'y') {
+		event.preventDefault();
+		redo();
+	}
+});
+
+function post_json(url, body) {
+	return fetch(url, {
+		method: 'POST',
+		headers: {
+			'Content-Type': 'application/json'
+		},
+		body: JSON.stringify(body)
+	})
+		.then(response => {
+			if (response.ok) {
+				return response.json();
+			} else {
+				return response.text().then(err => {
+					document.getElementById('status').textContent = err;
+				});
+			}
+		})
+		.catch(err => {
+			document.getElementById('status').textContent = 'HTTP Error: ' + err;
+		});
+}
+
+function update_widgets(values) {
+	if (values === undefined) {
+		return;
+	}
+
+	for (let {key, value, modified} of values) {
+		if (modified) {
+			set_widget_value(key, value);
+			show_error(key, '');
+
+			// Constants added after the page was loaded don't have a widget yet
+			let widget = document.querySelector('.widget[data-key="' + CSS.escape(key) + '"]');
+			if (widget) {
+				let data_type = widget.dataset.type == '&str' ? 'string' : widget.dataset.type;
+				changed_value(key, value, data_type);
+			}
+		} else {
+			reset_widget(key, value);
+		}
+	}
+
+	update_history();
+}
+
+function set_widget_value(source, value) {
+	// Update the widget without sending the value back
+	let input = document.getElementById(source);
+	if (input) {
+		if (input.type == 'checkbox') {
+			input.checked = value;
+		} else if (input.dataset.scale) {
+			input.value = scaled_position(input, value);
+		} else {
+			input.value = value;
+		}
+	}
+	let number_element = document.getElementById(source + '_number');
+	if (number_element) {
+		number_element.value = value;
+	}
+	let label_element = document.getElementById(source + '_label');
+	if (label_element) {
+		label_element.textContent = value;
+	}
+}
+
+function reset_widget(source, value) {
+	set_widget_value(source, value);
+	show_error(source, '');
+
+	// The value is the same as the source again so it's not a change anymore
+	changed_values.delete(source);
+	set_modified(source, false);
+	update_output(split_name(source).module);
+}
+
 function copy_text(source) {
 	// Select the text area
-	var output_text = document.getElementById(source + "_output");
+	var output_text = document.getElementById(source.split("::").join("_") + "_output");
 	output_text.select();
 	output_text.setSelectionRange(0, 99999); /*For mobile devices*/
 
@@ -74,6 +365,176 @@ function poll() {
 			if (body == "refresh") {
 				console.log("Reloading page");
 				location.reload();
+			} else {
+				// Changes can also be made from code or a watched file
+				update_values();
+			}
+		})
+		.catch(err => {
+			document.getElementById('status').textContent = 'HTTP Error: ' + err;
+		});
+}
+
+function update_values() {
+	fetch('/values')
+		.then(response => response.json())
+		.then(({generation, values, now, plots, metrics}) => {
+			if (last_generation !== null && generation != last_generation) {
+				update_widgets(values);
+			}
+			last_generation = generation;
+
+			// Metrics don't have a widget, so they get a plot of their own
+			for (let name of metrics) {
+				add_metric(name);
+			}
+
+			// Move the plots along with the time, also when nothing changed
+			for (let [source, samples] of Object.entries(plots)) {
+				draw_plot(source, samples, now);
+			}
+		})
+		.catch(err => {
+			document.getElementById('status').textContent = 'HTTP Error: ' + err;
+		});
+}
+
+// Poll every second, which also moves the plots
+setInterval(poll, 1000);
+
+function toggle_animation(source) {
+	let container = document.getElementById(source + '_animation');
+	if (container.style.display != 'none') {
+		stop_animation(source);
+		return;
+	}
+
+	// Start with a flat curve at the current value
+	let value = Number(document.getElementById(source + '_number').value);
+	send_keyframes(source, [
+		{time: 0, value: value, easing: 'linear'},
+		{time: 1, value: value, easing: 'linear'}
+	]);
+}
+
+function send_keyframes(source, keyframes) {
+	fetch('/animation/set', {
+		method: 'POST',
+		headers: {
+			'Content-Type': 'application/json'
+		},
+		body: JSON.stringify({key: source, keyframes: keyframes})
+	})
+		.then(response => {
+			if (response.ok) {
+				show_error(source, '');
+				return response.json().then(keyframes => render_keyframes(source, keyframes));
+			} else {
+				return response.text().then(err => show_error(source, err));
+			}
+		})
+		.catch(err => {
+			document.getElementById('status').textContent = 'HTTP Error: ' + err;
+		});
+}
+
+function stop_animation(source) {
+	post_json('/animation/stop', {key: source}).then(values => {
+		if (values !== undefined) {
+			document.getElementById(source + '_animation').style.display = 'none';
+			set_animated(source, false);
+			update_widgets(values);
+		}
+	});
+}
+
+function set_animated(source, animated) {
+	// The value is set by the animation, so it can't be changed directly
+	for (let id of [source, source + '_number']) {
+		let input = document.getElementById(id);
+		if (input) {
+			input.disabled = animated;
+		}
+	}
+}
+
+function keyframes_from_editor(source) {
+	let rows = document.querySelectorAll('#' + CSS.escape(source + '_animation') + ' tbody tr');
+
+	return [...rows].map(row => ({
+		time: Number(row.querySelector('.keyframe_time').value),
+		value: Number(row.querySelector('.keyframe_value').value),
+		easing: row.querySelector('.keyframe_easing').value
+	}));
+}
+
+function render_keyframes(source, keyframes) {
+	let container = document.getElementById(source + '_animation');
+	if (!container) {
+		return;
+	}
+	container.style.display = '';
+	set_animated(source, true);
+
+	let changed = () => send_keyframes(source, keyframes_from_editor(source));
+	let number_input = (class_name, value) => {
+		let input = document.createElement('input');
+		input.type = 'number';
+		input.step = 'any';
+		input.className = 'input is-small ' + class_name;
+		input.value = value;
+		input.onchange = changed;
+		return input;
+	};
+
+	let table = document.createElement('table');
+	table.className = 'table is-narrow';
+	table.createTHead().innerHTML = '<tr><th>Time (s)</th><th>Value</th><th>Easing to the next keyframe</th><th></th></tr>';
+	let body = table.createTBody();
+	for (let keyframe of keyframes) {
+		let row = body.insertRow();
+		row.insertCell().appendChild(number_input('keyframe_time', keyframe.time));
+		row.insertCell().appendChild(number_input('keyframe_value', keyframe.value));
+
+		let easing = document.createElement('select');
+		easing.className = 'keyframe_easing';
+		for (let name of ['linear', 'step', 'ease_in', 'ease_out', 'ease_in_out']) {
+			easing.add(new Option(name.replace(/_/g, ' '), name, false, name == keyframe.easing));
+		}
+		easing.onchange = changed;
+		let select = document.createElement('div');
+		select.className = 'select is-small';
+		select.appendChild(easing);
+		row.insertCell().appendChild(select);
+
+		let remove = document.createElement('button');
+		remove.className = 'button is-small';
+		remove.textContent = 'Remove';
+		remove.onclick = () => {
+			row.remove();
+			changed();
+		};
+		row.insertCell().appendChild(remove);
+	}
+
+	// Add a keyframe a second after the last one, with the same value
+	let add = document.createElement('button');
+	add.className = 'button is-small';
+	add.textContent = 'Add keyframe';
+	add.onclick = () => {
+		let last = keyframes[keyframes.length - 1];
+		send_keyframes(source, keyframes.concat([{time: last.time + 1, value: last.value, easing: 'linear'}]));
+	};
+
+	container.replaceChildren(table, add);
+}
+
+function update_animations() {
+	fetch('/animations')
+		.then(response => response.json())
+		.then(animations => {
+			for (let {key, keyframes} of animations) {
+				render_keyframes(key, keyframes);
 			}
 		})
 		.catch(err => {
@@ -81,5 +542,151 @@ function poll() {
 		});
 }
 
-// Poll every 3 seconds
-setInterval(poll, 3000);
+// The plots show the changes of the last minute
+const PLOT_WINDOW = 60 * 1000;
+
+function add_metric(name) {
+	if (document.getElementById(name + '_plot')) {
+		return;
+	}
+
+	let row = document.createElement('div');
+	row.className = 'columns is-vcentered';
+	let label = document.createElement('div');
+	label.className = 'column is-3';
+	label.textContent = name;
+	let value = document.createElement('div');
+	value.className = 'column is-2';
+	value.id = name + '_metric';
+
+	let svg_namespace = 'http://www.w3.org/2000/svg';
+	let svg = document.createElementNS(svg_namespace, 'svg');
+	svg.id = name + '_plot';
+	svg.setAttribute('width', '240');
+	svg.setAttribute('height', '32');
+	svg.setAttribute('viewBox', '0 0 240 32');
+	let polyline = document.createElementNS(svg_namespace, 'polyline');
+	polyline.setAttribute('fill', 'none');
+	polyline.setAttribute('stroke', '#3273dc');
+	polyline.setAttribute('stroke-width', '1.5');
+	svg.appendChild(polyline);
+	let plot = document.createElement('div');
+	plot.className = 'column is-narrow';
+	plot.appendChild(svg);
+
+	row.append(label, value, plot);
+	document.getElementById('metrics').appendChild(row);
+	document.getElementById('metrics_section').style.display = '';
+}
+
+function draw_plot(source, samples, now) {
+	let svg = document.getElementById(source + '_plot');
+	if (svg == null || samples.length == 0) {
+		return;
+	}
+
+	// Keep the last change from before the window, so the line starts at the left edge
+	let start = now - PLOT_WINDOW;
+	let first = samples.findIndex(([time, _]) => time >= start);
+	let visible = first == -1 ? samples.slice(-1) : samples.slice(Math.max(first - 1, 0));
+
+	let values = visible.map(([_, value]) => value);
+	let min = Math.min(...values);
+	let max = Math.max(...values);
+	let width = svg.viewBox.baseVal.width;
+	let height = svg.viewBox.baseVal.height;
+	let x = time => (Math.max(time, start) - start) / PLOT_WINDOW * width;
+	let y = value => max == min ? height / 2 : height - 1 - (value - min) / (max - min) * (height - 2);
+
+	// The value stays the same until the next change, so draw it as steps
+	let points = [];
+	visible.forEach(([time, value], index) => {
+		if (index > 0) {
+			points.push(x(time) + ',' + y(values[index - 1]));
+		}
+		points.push(x(time) + ',' + y(value));
+	});
+	points.push(width + ',' + y(values[values.length - 1]));
+
+	svg.querySelector('polyline').setAttribute('points', points.join(' '));
+	svg.parentElement.title = 'From ' + min + ' to ' + max;
+
+	// Metrics show their last value next to the plot
+	let metric = document.getElementById(source + '_metric');
+	if (metric) {
+		metric.textContent = samples[samples.length - 1][1];
+	}
+}
+
+function pinned_keys() {
+	return new Set(JSON.parse(localStorage.getItem('const_tweaker_pinned') || '[]'));
+}
+
+function toggle_pin(source) {
+	let pinned = pinned_keys();
+	if (pinned.has(source)) {
+		pinned.delete(source);
+	} else {
+		pinned.add(source);
+	}
+	localStorage.setItem('const_tweaker_pinned', JSON.stringify([...pinned]));
+
+	update_pins();
+	filter_widgets();
+}
+
+function update_pins() {
+	let pinned = pinned_keys();
+	for (let widget of document.querySelectorAll('.widget')) {
+		let button = document.getElementById(widget.dataset.key + '_pin');
+		if (button) {
+			button.textContent = pinned.has(widget.dataset.key) ? '★' : '☆';
+		}
+	}
+}
+
+function filter_widgets() {
+	let search = document.getElementById('filter_search').value.toLowerCase();
+	let modified_only = document.getElementById('filter_modified').checked;
+	let pinned_only = document.getElementById('filter_pinned').checked;
+	let pinned = pinned_keys();
+
+	// Hide the widgets that don't match, they are hidden instead of removed so the slider state is kept
+	for (let widget of document.querySelectorAll('.widget')) {
+		let key = widget.dataset.key;
+		let name = document.getElementById(key + '_name');
+		let haystack = [
+			name ? name.textContent : key,
+			document.getElementById(key + '_label')?.textContent,
+			widget.dataset.module,
+			widget.dataset.file,
+			widget.dataset.type
+		].join(' ').toLowerCase();
+
+		let visible = haystack.includes(search)
+			&& (!modified_only || widget.dataset.modified == 'true')
+			&& (!pinned_only || pinned.has(key));
+		widget.style.display = visible ? '' : 'none';
+	}
+
+	// Hide the modules without any visible widgets
+	for (let module of document.querySelectorAll('.module')) {
+		let any_visible = [...module.querySelectorAll('.widget')]
+			.some(widget => widget.style.display != 'none');
+		module.style.display = any_visible ? '' : 'none';
+	}
+}
+
+function init_scaled_sliders() {
+	// The position of non-linear sliders can only be calculated from the value
+	for (let slider of document.querySelectorAll('input[data-scale]')) {
+		let number_element = document.getElementById(slider.id + '_number');
+		slider.value = scaled_position(slider, Number(number_element.value));
+	}
+}
+
+update_pins();
+init_scaled_sliders();
+update_history();
+update_values();
+update_animations();
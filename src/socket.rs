@@ -0,0 +1,123 @@
+use async_std::{os::unix::net::UnixListener, task};
+use futures::{future::FutureObj, task::Spawn};
+use std::{
+    fs, io,
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::UnixStream,
+    },
+    path::Path,
+};
+
+/// Spawns the tasks handling the connections on the async-std executor.
+#[derive(Debug, Copy, Clone)]
+struct Spawner;
+
+impl Spawn for &Spawner {
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), futures::task::SpawnError> {
+        task::spawn(future);
+
+        Ok(())
+    }
+}
+
+/// Serve the app on a Unix domain socket instead of a TCP port.
+pub(crate) async fn listen(app: tide::Server<()>, path: &Path) -> io::Result<()> {
+    let listener = bind(path).await?;
+
+    http_service_hyper::Server::builder(listener.incoming())
+        .with_spawner(Spawner)
+        .serve(app.into_http_service())
+        .await
+        .map_err(io::Error::other)
+}
+
+/// Create the socket, only accessible by the current user.
+async fn bind(path: &Path) -> io::Result<UnixListener> {
+    // A socket left behind by a previous run can't be bound to again, other files are never
+    // removed
+    if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        // Only remove it when nothing is listening on it anymore, otherwise another running
+        // application would lose its socket
+        match UnixStream::connect(path) {
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("another application is listening on {}", path.display()),
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(path)?,
+            Err(err) => return Err(err),
+        }
+    }
+
+    let listener = UnixListener::bind(path).await?;
+    // Connecting requires write permission, the umask usually allows other users to do that
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+    Ok(listener)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{os::unix::net::UnixListener as StdUnixListener, path::PathBuf};
+
+    /// A path in the temporary directory, unique for the test.
+    fn socket_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "const-tweaker-{}-{}.sock",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+
+        path
+    }
+
+    #[test]
+    fn only_accessible_by_the_user() {
+        let path = socket_path("mode");
+        let _listener = task::block_on(bind(&path)).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn stale_socket_is_replaced() {
+        let path = socket_path("stale");
+        // The file stays behind when the listener is dropped
+        drop(StdUnixListener::bind(&path).unwrap());
+
+        let _listener = task::block_on(bind(&path)).unwrap();
+        assert!(UnixStream::connect(&path).is_ok());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn socket_in_use() {
+        let path = socket_path("in_use");
+        let _other = StdUnixListener::bind(&path).unwrap();
+
+        let err = task::block_on(bind(&path)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert!(path.exists());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn other_files_are_kept() {
+        let path = socket_path("file");
+        fs::write(&path, "not a socket").unwrap();
+
+        assert!(task::block_on(bind(&path)).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not a socket");
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,489 @@
+use crate::{api, notify, Descriptor, Error, Origin, Value, DATA};
+use std::collections::BTreeMap;
+
+/// The minimum, maximum & step of the values to try.
+type Range = (f64, f64, f64);
+
+/// The most combinations tried when trying every combination, more need [`Sweep::random`].
+const MAX_COMBINATIONS: u64 = 1_000_000;
+
+/// Tries combinations of values for constants and scores each of them with a closure, to find the
+/// best values without moving the sliders by hand.
+///
+/// By default every combination of the values is tried, from the minimum to the maximum of each
+/// slider in steps of the slider. With [`random`](Sweep::random) only a number of random
+/// combinations is tried, which is better when there are many constants.
+///
+/// ```rust
+/// #[const_tweaker::tweak(min = 0.0, max = 1.0, step = 0.1)]
+/// const FRICTION: f64 = 0.5;
+///
+/// #[const_tweaker::tweak(min = 0, max = 10)]
+/// const ITERATIONS: i32 = 1;
+///
+/// # let friction = concat!(module_path!(), "::FRICTION");
+/// # let iterations = concat!(module_path!(), "::ITERATIONS");
+/// let results = const_tweaker::Sweep::new()
+///     .field(friction)
+///     .field(iterations)
+///     // The closure reads the constants like the game does, higher scores are better
+///     .run(|| -(*FRICTION - 0.3).abs() - (*ITERATIONS - 7).abs() as f64)
+///     .unwrap();
+///
+/// let best = &results[0];
+/// assert_eq!(best.score, 0.0);
+/// // Prints the lines to paste in the source, e.g. `const FRICTION: f64 = 0.3;`
+/// println!("{}", best.to_rust());
+///
+/// // The values from before the sweep are restored
+/// assert_eq!(*FRICTION, 0.5);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Sweep {
+    /// The keys with the minimum, maximum & step, `None` to use the ones of the slider.
+    ranges: Vec<(String, Option<Range>)>,
+    /// The number of random combinations & the seed, `None` to try every combination.
+    random: Option<(usize, u64)>,
+}
+
+/// A combination of values tried by a [`Sweep`] with its score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combination {
+    /// The values by key, in the order they were added to the sweep.
+    pub values: Vec<(String, Value)>,
+    /// The value returned by the closure.
+    pub score: f64,
+}
+
+/// The values a single constant takes during a sweep.
+#[derive(Debug, Clone)]
+struct Axis {
+    key: String,
+    /// The type of the constant, used to convert the numbers.
+    kind: Value,
+    /// The minimum & step, `None` for booleans which are tried as `false` & `true`.
+    range: Option<(f64, f64)>,
+    /// How many values are tried.
+    count: u64,
+}
+
+impl Axis {
+    /// Describe the values to try for the constant, from the range or the slider.
+    fn new(key: &str, range: Option<Range>) -> Result<Self, Error> {
+        let rejected = |reason: &str| Error::Rejected {
+            key: key.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let field = DATA
+            .get(key)
+            .ok_or_else(|| Error::UnknownKey(key.to_string()))?;
+        let descriptor = Descriptor::new(key, &field);
+
+        let as_f64 = |value: &Option<Value>| value.as_ref()?.to_json().as_f64();
+        let slider = (
+            as_f64(&descriptor.min).unwrap_or(f64::NAN),
+            as_f64(&descriptor.max).unwrap_or(f64::NAN),
+            as_f64(&descriptor.step).unwrap_or(f64::NAN),
+        );
+        let (min, max, step) = match (&descriptor.value, range) {
+            (Value::Bool(_), _) => {
+                return Ok(Axis {
+                    key: key.to_string(),
+                    kind: descriptor.value,
+                    range: None,
+                    count: 2,
+                })
+            }
+            (Value::String(_), _) => return Err(rejected("strings can't be swept")),
+            (_, Some(range)) => range,
+            (_, None) => slider,
+        };
+
+        if !min.is_finite() || !max.is_finite() || !step.is_finite() {
+            return Err(rejected("the range of the sweep is not a number"));
+        }
+        if min > max || step <= 0.0 {
+            return Err(rejected(&format!(
+                "can't sweep from {} to {} in steps of {}",
+                min, max, step
+            )));
+        }
+
+        // The values outside of the slider would all be rejected when they're set
+        if !descriptor.allow_out_of_range && (min < slider.0 || max > slider.1) {
+            return Err(rejected(&format!(
+                "can't sweep from {} to {} outside of the range {} to {}",
+                min, max, slider.0, slider.1
+            )));
+        }
+
+        // Allow a bit of floating point error so the maximum itself is included
+        let count = ((max - min) / step + 1e-9).floor() as u64 + 1;
+
+        Ok(Axis {
+            key: key.to_string(),
+            kind: descriptor.value,
+            range: Some((min, step)),
+            count,
+        })
+    }
+
+    /// The value at the index as JSON, converted with the type of the field when it's set.
+    fn value_json(&self, index: u64) -> serde_json::Value {
+        match self.range {
+            Some((min, step)) => {
+                let value = min + step * index as f64;
+                // Get rid of floating point errors introduced by the steps
+                let value = format!("{:.11e}", value).parse().unwrap_or(value);
+
+                self.kind.number_json(value)
+            }
+            None => (index == 1).into(),
+        }
+    }
+}
+
+/// Puts back the values from before the sweep when it goes out of scope, also when the closure
+/// panics, and notifies the subscribers of them.
+struct Restore {
+    values: Vec<(String, Value)>,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        for (key, value) in &self.values {
+            // Skip the validation, the validation function might reject the value by now
+            if let Some(mut field) = DATA.get_mut(&**key) {
+                field.restore_value(value.clone());
+            }
+        }
+
+        // The fields must be unlocked before notifying, so the callbacks can read them
+        for (key, _) in &self.values {
+            notify(key);
+        }
+    }
+}
+
+/// A small pseudo random number generator, so sweeps with the same seed try the same values.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// A random number below the bound, the bias from the modulo is negligible for sweeps.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+        (z ^ (z >> 31)) % bound
+    }
+}
+
+impl Sweep {
+    /// Create a sweep without any constants.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sweep a constant over the range of its slider, booleans are tried as `false` & `true`.
+    pub fn field(mut self, key: &str) -> Self {
+        self.ranges.push((key.to_string(), None));
+
+        self
+    }
+
+    /// Sweep a numeric constant from the minimum to the maximum in steps, instead of the range of
+    /// its slider.
+    ///
+    /// The range has to be inside of the range of the slider, unless the constant allows values
+    /// outside of it.
+    pub fn range(mut self, key: &str, min: f64, max: f64, step: f64) -> Self {
+        self.ranges.push((key.to_string(), Some((min, max, step))));
+
+        self
+    }
+
+    /// Try a number of random combinations instead of every combination.
+    ///
+    /// The same seed tries the same combinations every time.
+    pub fn random(mut self, samples: usize, seed: u64) -> Self {
+        self.random = Some((samples, seed));
+
+        self
+    }
+
+    /// Set the constants to every combination in turn and call the closure to score it, higher
+    /// scores are better.
+    ///
+    /// The values are set globally so other threads see them too, but subscribers aren't notified
+    /// of every combination and the history isn't changed: the closure has to read the constants
+    /// itself. When the sweep is done the values from before it are restored & the subscribers are
+    /// notified once, use [`Combination::apply`] to keep the best ones.
+    ///
+    /// Returns the scored combinations sorted from best to worst. Combinations rejected by a
+    /// validation function and `NaN` scores are left out.
+    ///
+    /// Trying every combination is refused when there are more than a million, e.g. for integers
+    /// without a range on the slider: pass a smaller [`range`](Sweep::range) or use
+    /// [`random`](Sweep::random).
+    pub fn run<F>(&self, mut score: F) -> Result<Vec<Combination>, Error>
+    where
+        F: FnMut() -> f64,
+    {
+        let axes = self
+            .ranges
+            .iter()
+            .map(|(key, range)| Axis::new(key, *range))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if self.random.is_none() {
+            let combinations = axes.iter().fold(1u64, |combinations, axis| {
+                combinations.saturating_mul(axis.count)
+            });
+            if let Some(axis) = axes
+                .iter()
+                .max_by_key(|axis| axis.count)
+                .filter(|_| combinations > MAX_COMBINATIONS)
+            {
+                return Err(Error::Rejected {
+                    key: axis.key.clone(),
+                    reason: format!(
+                        "trying every combination would take more than {} tries, sweep a smaller \
+                         range or a number of random combinations",
+                        MAX_COMBINATIONS
+                    ),
+                });
+            }
+        }
+
+        let _restore = Restore {
+            values: axes
+                .iter()
+                .filter_map(|axis| Some((axis.key.clone(), DATA.get(&*axis.key)?.current_value())))
+                .collect(),
+        };
+
+        let mut results = vec![];
+        let mut try_combination = |indices: &[u64]| {
+            if let Some(values) = set_combination(&axes, indices) {
+                let score = score();
+                if !score.is_nan() {
+                    results.push(Combination { values, score });
+                }
+            }
+        };
+
+        match self.random {
+            Some((samples, seed)) => {
+                let mut rng = SplitMix64(seed);
+                for _ in 0..samples {
+                    let indices = axes
+                        .iter()
+                        .map(|axis| rng.below(axis.count))
+                        .collect::<Vec<_>>();
+                    try_combination(&indices);
+                }
+            }
+            None => {
+                // Count through all combinations like an odometer, the last axis changes fastest
+                let mut indices = vec![0; axes.len()];
+                'combinations: loop {
+                    try_combination(&indices);
+
+                    for (index, axis) in indices.iter_mut().zip(&axes).rev() {
+                        *index += 1;
+                        if *index < axis.count {
+                            continue 'combinations;
+                        }
+                        *index = 0;
+                    }
+
+                    break;
+                }
+            }
+        }
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        Ok(results)
+    }
+}
+
+impl Combination {
+    /// The values as Rust constants to paste in the source, grouped by module.
+    pub fn to_rust(&self) -> String {
+        let mut modules = BTreeMap::<&str, Vec<String>>::new();
+        for (key, value) in &self.values {
+            let (module, name) = key.rsplit_once("::").unwrap_or(("", key));
+            modules.entry(module).or_default().push(format!(
+                "const {}: {} = {};",
+                name,
+                value.type_name(),
+                value.to_rust()
+            ));
+        }
+
+        modules
+            .into_iter()
+            .map(|(module, lines)| format!("// {}\n{}\n", module, lines.join("\n")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Set the constants to the values, like [`set`](crate::set) does.
+    pub fn apply(&self) -> Result<(), Error> {
+        for (key, value) in &self.values {
+            api::set_from(key, value.clone(), Origin::Api)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Set all constants to the values at the indices, `None` when any of them is rejected.
+fn set_combination(axes: &[Axis], indices: &[u64]) -> Option<Vec<(String, Value)>> {
+    axes.iter()
+        .zip(indices)
+        .map(|(axis, index)| {
+            let value = DATA
+                .get(&*axis.key)?
+                .value_from_json(axis.value_json(*index))
+                .ok()?;
+            api::set_checked(&axis.key, value.clone()).ok()?;
+
+            Some((axis.key.clone(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{subscribe, test_field, Field, Scale};
+    use std::{
+        panic,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    fn value(key: &str) -> Value {
+        DATA.get(key).unwrap().current_value()
+    }
+
+    #[test]
+    fn every_combination() {
+        test_field("sweep::tests::EVERY_A", None);
+        test_field("sweep::tests::EVERY_B", None);
+
+        let results = Sweep::new()
+            .range("sweep::tests::EVERY_A", 0.0, 1.0, 0.5)
+            .range("sweep::tests::EVERY_B", 2.0, 4.0, 1.0)
+            .run(|| 0.0)
+            .unwrap();
+
+        assert_eq!(results.len(), 9);
+    }
+
+    #[test]
+    fn best_first() {
+        test_field("sweep::tests::BEST", None);
+        let key = "sweep::tests::BEST";
+
+        let results = Sweep::new()
+            .range(key, 0.0, 10.0, 1.0)
+            .run(|| match value(key) {
+                Value::F64(value) => -(value - 7.0).abs(),
+                _ => f64::NAN,
+            })
+            .unwrap();
+
+        assert_eq!(results[0].values, [(key.to_string(), Value::F64(7.0))]);
+        assert_eq!(results.len(), 11);
+        assert_eq!(value(key), Value::F64(1.0));
+    }
+
+    #[test]
+    fn seeded_repeatability() {
+        test_field("sweep::tests::SEEDED", None);
+        let sweep = |seed| {
+            Sweep::new()
+                .field("sweep::tests::SEEDED")
+                .random(20, seed)
+                .run(|| 0.0)
+                .unwrap()
+                .into_iter()
+                .map(|combination| combination.values)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sweep(1), sweep(1));
+        assert_ne!(sweep(1), sweep(2));
+        assert_eq!(sweep(1).len(), 20);
+    }
+
+    #[test]
+    fn rejected_ranges() {
+        test_field("sweep::tests::RANGES", None);
+        let key = "sweep::tests::RANGES";
+        let run = |min, max, step| Sweep::new().range(key, min, max, step).run(|| 0.0);
+
+        assert!(run(2.0, 1.0, 0.1).is_err());
+        assert!(run(0.0, 1.0, 0.0).is_err());
+        assert!(run(0.0, f64::NAN, 0.1).is_err());
+        // Outside of the slider from 0 to 10
+        assert!(run(5.0, 20.0, 1.0).is_err());
+        assert!(matches!(
+            Sweep::new().field("sweep::tests::MISSING").run(|| 0.0),
+            Err(Error::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn too_many_combinations() {
+        DATA.insert(
+            "sweep::tests::HUGE",
+            Field::I32 {
+                value: 0,
+                default: 0,
+                min: i32::MIN,
+                max: i32::MAX,
+                step: 1,
+                allow_out_of_range: false,
+                scale: Scale::Linear,
+                validate: None,
+                module: "sweep::tests".to_string(),
+                file: file!().to_string(),
+                line: line!(),
+            },
+        );
+        let sweep = Sweep::new().field("sweep::tests::HUGE");
+
+        assert!(matches!(sweep.run(|| 0.0), Err(Error::Rejected { .. })));
+        assert_eq!(sweep.random(10, 0).run(|| 0.0).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn restored_after_panic() {
+        test_field("sweep::tests::PANIC", None);
+        let key = "sweep::tests::PANIC";
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        subscribe(key, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let result = panic::catch_unwind(|| {
+            Sweep::new()
+                .range(key, 5.0, 6.0, 1.0)
+                .run(|| panic!("test"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(value(key), Value::F64(1.0));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
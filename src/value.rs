@@ -0,0 +1,174 @@
+use serde::Serialize;
+use std::{convert::TryFrom, fmt};
+
+/// A value of any of the supported types.
+///
+/// Every supported type can be converted into it with `From`, and back with `TryFrom`:
+///
+/// ```rust
+/// use const_tweaker::Value;
+/// use std::convert::TryFrom;
+///
+/// let value = Value::from(0.5f32);
+/// assert_eq!(value.type_name(), "f32");
+/// assert_eq!(f32::try_from(value), Ok(0.5));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    F32(f32),
+    F64(f64),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    Usize(usize),
+    Bool(bool),
+    String(String),
+}
+
+impl Value {
+    /// The Rust type of the value as it would be written in the source.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::F32(_) => "f32",
+            Value::F64(_) => "f64",
+            Value::I8(_) => "i8",
+            Value::U8(_) => "u8",
+            Value::I16(_) => "i16",
+            Value::U16(_) => "u16",
+            Value::I32(_) => "i32",
+            Value::U32(_) => "u32",
+            Value::I64(_) => "i64",
+            Value::U64(_) => "u64",
+            Value::Usize(_) => "usize",
+            Value::Bool(_) => "bool",
+            Value::String(_) => "&str",
+        }
+    }
+
+    /// The value as a JSON value.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            // Go through the shortest string representation so `0.1f32` doesn't become
+            // `0.10000000149011612`
+            Value::F32(value) => value
+                .to_string()
+                .parse::<f64>()
+                .map(Into::into)
+                .unwrap_or(serde_json::Value::Null),
+            Value::F64(value) => (*value).into(),
+            Value::I8(value) => (*value).into(),
+            Value::U8(value) => (*value).into(),
+            Value::I16(value) => (*value).into(),
+            Value::U16(value) => (*value).into(),
+            Value::I32(value) => (*value).into(),
+            Value::U32(value) => (*value).into(),
+            Value::I64(value) => (*value).into(),
+            Value::U64(value) => (*value).into(),
+            Value::Usize(value) => (*value).into(),
+            Value::Bool(value) => (*value).into(),
+            Value::String(value) => value.clone().into(),
+        }
+    }
+
+    /// A JSON number of the same kind as this value, integers are rounded so they aren't written
+    /// as floats.
+    pub(crate) fn number_json(&self, number: f64) -> serde_json::Value {
+        match self {
+            Value::F32(_) | Value::F64(_) => number.into(),
+            _ if number < 0.0 => (number.round() as i64).into(),
+            _ => (number.round() as u64).into(),
+        }
+    }
+
+    /// Whether the values are the same, unlike `==` a NaN is the same as the same NaN.
+    pub(crate) fn is_same(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::F32(value), Value::F32(other)) => value.to_bits() == other.to_bits(),
+            (Value::F64(value), Value::F64(other)) => value.to_bits() == other.to_bits(),
+            (value, other) => value == other,
+        }
+    }
+
+    /// The value as a Rust literal, floats always have a decimal point and strings are quoted.
+    pub(crate) fn to_rust(&self) -> String {
+        match self {
+            Value::F32(value) => format!("{:?}", value),
+            Value::F64(value) => format!("{:?}", value),
+            Value::String(value) => format!("{:?}", value),
+            value => value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::F32(value) => write!(f, "{}", value),
+            Value::F64(value) => write!(f, "{}", value),
+            Value::I8(value) => write!(f, "{}", value),
+            Value::U8(value) => write!(f, "{}", value),
+            Value::I16(value) => write!(f, "{}", value),
+            Value::U16(value) => write!(f, "{}", value),
+            Value::I32(value) => write!(f, "{}", value),
+            Value::U32(value) => write!(f, "{}", value),
+            Value::I64(value) => write!(f, "{}", value),
+            Value::U64(value) => write!(f, "{}", value),
+            Value::Usize(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Implement the conversions from and to a value for a primitive type.
+macro_rules! impl_conversions {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Value::$variant(value)
+                }
+            }
+
+            impl TryFrom<Value> for $ty {
+                /// The value is returned when it's a different type.
+                type Error = Value;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    match value {
+                        Value::$variant(value) => Ok(value),
+                        value => Err(value),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_conversions!(
+    f32 => F32,
+    f64 => F64,
+    i8 => I8,
+    u8 => U8,
+    i16 => I16,
+    u16 => U16,
+    i32 => I32,
+    u32 => U32,
+    i64 => I64,
+    u64 => U64,
+    usize => Usize,
+    bool => Bool,
+    String => String
+);
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{history, notify, preset, Error, Origin, DATA};
+use std::{collections::BTreeMap, fs, path::Path, thread, time::Duration};
+
+/// How often the file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The values in the file by key.
+type Values = BTreeMap<String, serde_json::Value>;
+
+/// Apply the values from a TOML or RON file every time it's saved.
+///
+/// The file maps the full paths of the constants to their values, nested tables are joined with
+/// `::`. The format is chosen by the extension, `.ron` files are read as RON and all others as
+/// TOML:
+///
+/// ```toml
+/// "game::DEBUG" = true
+///
+/// [game.physics]
+/// GRAVITY = 9.8
+/// ```
+///
+/// Only the values that changed in the file since it was last read are applied, so values
+/// changed in the web interface in the meantime are kept.
+/// When the file can't be parsed or any of the changed values is invalid none of them are
+/// applied and the error is printed to stderr, it's tried again on the next save.
+///
+/// ```rust,no_run
+/// const_tweaker::watch_file("tweaks.toml");
+/// ```
+pub fn watch_file<P>(path: P)
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref().to_path_buf();
+
+    thread::spawn(move || {
+        let mut last_modified = None;
+        let mut last_values = Values::new();
+
+        loop {
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if modified.is_some() && modified != last_modified {
+                last_modified = modified;
+
+                match reload(&path, &last_values) {
+                    Ok(values) => last_values = values,
+                    Err(err) => eprintln!("const-tweaker: {}: {}", path.display(), err),
+                }
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Read the file and apply the values that changed, returns all values in the file.
+fn reload(path: &Path, last_values: &Values) -> Result<Values, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let values = parse(path, &contents)?;
+
+    // Convert all values first so an invalid value doesn't leave a partially applied file
+    let changed = values
+        .iter()
+        .filter(|(key, json)| last_values.get(*key) != Some(json))
+        .map(|(key, json)| {
+            let field = DATA
+                .get(&**key)
+                .ok_or_else(|| Error::UnknownKey(key.clone()))?;
+            let key = *field.key();
+
+            field
+                .value_from_json(json.clone())
+                .map(|value| (key, value))
+                .map_err(|reason| Error::Rejected {
+                    key: key.to_string(),
+                    reason,
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+
+    let old_values = preset::apply_all(changed).map_err(|err| err.to_string())?;
+
+    // The fields must be unlocked before notifying, so the callbacks can read them
+    for (key, old_value) in old_values {
+        let new_value = match DATA.get(key) {
+            Some(field) => field.current_value(),
+            None => continue,
+        };
+        if new_value != old_value {
+            history::record(key, old_value, new_value, Origin::File);
+            notify(key);
+        }
+    }
+
+    Ok(values)
+}
+
+/// Parse the contents by the extension of the file.
+fn parse(path: &Path, contents: &str) -> Result<Values, String> {
+    let json: serde_json::Value = if path.extension().is_some_and(|extension| extension == "ron") {
+        ron::from_str(contents).map_err(|err| err.to_string())?
+    } else {
+        toml::from_str(contents).map_err(|err| err.to_string())?
+    };
+
+    let mut values = Values::new();
+    flatten(&mut values, "", json);
+
+    Ok(values)
+}
+
+/// Join the keys of nested tables with `::`.
+fn flatten(values: &mut Values, prefix: &str, json: serde_json::Value) {
+    match json {
+        serde_json::Value::Object(map) => {
+            for (key, json) in map {
+                if prefix.is_empty() {
+                    flatten(values, &key, json);
+                } else {
+                    flatten(values, &format!("{}::{}", prefix, key), json);
+                }
+            }
+        }
+        json => {
+            values.insert(prefix.to_string(), json);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_history, test_field};
+    use std::path::PathBuf;
+
+    /// The current value of a constant as JSON.
+    fn value(key: &str) -> serde_json::Value {
+        DATA.get(key).unwrap().value_json()
+    }
+
+    /// Write a file in the temporary directory, unique for the test.
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("const-tweaker-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn parse_flattens_tables() {
+        let values = parse(
+            Path::new("tweaks.toml"),
+            "\"game::DEBUG\" = true\n[game.physics]\nGRAVITY = 9.8\n",
+        )
+        .unwrap();
+
+        assert_eq!(values["game::DEBUG"], true);
+        assert_eq!(values["game::physics::GRAVITY"], 9.8);
+        assert_eq!(values.len(), 2);
+    }
+
+    #[test]
+    fn parse_ron_by_extension() {
+        let values = parse(Path::new("tweaks.ron"), "{\"game::SPEED\": 2.5}").unwrap();
+
+        assert_eq!(values["game::SPEED"], 2.5);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse(Path::new("tweaks.toml"), "GRAVITY = ").is_err());
+        assert!(parse(Path::new("tweaks.ron"), "{").is_err());
+    }
+
+    #[test]
+    fn reload_missing_file() {
+        let path = std::env::temp_dir().join("const-tweaker-does-not-exist.toml");
+
+        assert!(reload(&path, &Values::new()).is_err());
+    }
+
+    #[test]
+    fn reload_applies_changed_values() {
+        let _history = lock_history();
+        test_field("watch::tests::APPLIED", None);
+        let path = write("applied.toml", "\"watch::tests::APPLIED\" = 2.0\n");
+
+        let values = reload(&path, &Values::new()).unwrap();
+        assert_eq!(value("watch::tests::APPLIED"), 2.0);
+
+        // A value that didn't change in the file doesn't overwrite a change made elsewhere
+        DATA.get_mut("watch::tests::APPLIED")
+            .unwrap()
+            .set_value(crate::Value::F64(3.0))
+            .unwrap();
+        reload(&path, &values).unwrap();
+        assert_eq!(value("watch::tests::APPLIED"), 3.0);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reload_unknown_key() {
+        test_field("watch::tests::UNKNOWN", None);
+        let path = write(
+            "unknown.toml",
+            "\"watch::tests::UNKNOWN\" = 2.0\n\"watch::tests::MISSING\" = 2.0\n",
+        );
+
+        let err = reload(&path, &Values::new()).unwrap_err();
+        assert!(err.contains("watch::tests::MISSING"), "{}", err);
+        // Nothing is applied when any of the values is wrong
+        assert_eq!(value("watch::tests::UNKNOWN"), 1.0);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reload_wrong_type() {
+        test_field("watch::tests::WRONG_TYPE", None);
+        let path = write(
+            "wrong_type.toml",
+            "\"watch::tests::WRONG_TYPE\" = \"fast\"\n",
+        );
+
+        assert!(reload(&path, &Values::new()).is_err());
+        assert_eq!(value("watch::tests::WRONG_TYPE"), 1.0);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reload_rejected_restores_applied_values() {
+        test_field("watch::tests::ACCEPTED", None);
+        test_field(
+            "watch::tests::REJECTED",
+            Some(|value| {
+                if *value > 5.0 {
+                    Err("too fast".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        );
+        let path = write(
+            "rejected.toml",
+            "[watch.tests]\nACCEPTED = 2.0\nREJECTED = 6.0\n",
+        );
+
+        let err = reload(&path, &Values::new()).unwrap_err();
+        assert!(err.contains("too fast"), "{}", err);
+        assert_eq!(value("watch::tests::ACCEPTED"), 1.0);
+        assert_eq!(value("watch::tests::REJECTED"), 1.0);
+
+        fs::remove_file(path).unwrap();
+    }
+}